@@ -1,9 +1,29 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
 use clap::{Parser, Subcommand, Args, ValueEnum};
+
+use crate::filter::{parse_project, parse_tag};
+use crate::output::{Column, Format};
+use crate::tasks::Priority;
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)] // Will read from Cargo.toml
 pub struct Cli {
-    #[arg(value_enum)]
-    pub list: Lists,
+    /// List to manage, goals, day, week, month, quarter, year or a list made with `pask list create`. The
+    /// configured default list when left out
+    #[arg(value_name = "LIST")]
+    pub list: Option<ListArg>,
+    /// List to manage, the same as giving it before the command
+    #[arg(short, long = "list", global = true, value_name = "LIST")]
+    pub list_flag: Option<ListArg>,
+    /// Directory to keep lists in, overrides PASK_HOME
+    #[arg(long, global = true, value_name = "DIR")]
+    pub data_dir: Option<PathBuf>,
+    /// Date whose day, week, month, quarter or year list to use, such as 2026-11-03, tomorrow, -1w
+    /// or next month
+    #[arg(long, global = true, allow_hyphen_values = true, value_name = "DATE")]
+    pub date: Option<String>,
     #[clap(subcommand)] 
     pub command: Commands,
 }
@@ -18,6 +38,56 @@ pub enum Lists {
     Week,
     /// Monthly list 
     Month,
+    /// Quarterly list
+    Quarter,
+    /// Yearly list
+    Year,
+}
+
+/// A list given on the command line or in the config file.
+#[derive(Clone, PartialEq, Eq)]
+pub enum ListArg {
+    /// One of the built in lists
+    Kind(Lists),
+    /// A list made with `pask list create`
+    Named(String),
+}
+
+/// Built in lists are matched first, anything else has to be a valid list name.
+impl FromStr for ListArg {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        if let Ok(kind) = Lists::from_str(text, true) {
+            return Ok(ListArg::Kind(kind));
+        }
+        check_list_name(text)?;
+        Ok(ListArg::Named(text.to_owned()))
+    }
+}
+
+impl fmt::Display for ListArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListArg::Kind(kind) => {
+                write!(f, "{}", kind.to_possible_value().map_or(String::new(), |x| x.get_name().to_owned()))
+            },
+            ListArg::Named(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Makes sure a name can be used for a list, names are lowercase letters, digits, `-` and `_`.
+pub fn check_list_name(name: &str) -> Result<(), String> {
+    let valid_char = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_';
+    if name.is_empty() || name.len() > 64 || !name.chars().all(valid_char) || name.starts_with('-') {
+        return Err(format!(
+            "{} is not a list, use goals, day, week, month, quarter, year or a name of lowercase letters, \
+             digits, - and _",
+            name
+        ));
+    }
+    Ok(())
 }
 
 #[derive(Subcommand)]
@@ -28,29 +98,182 @@ pub enum Commands {
     Delete(Delete), 
     /// Change a task from incomplete to Complete
     Complete(Complete),
-    /// Display the lists of tasks 
-    Display,
-    /// Open an interactive interface
+    /// Mark a task as in progress
+    Start(Select),
+    /// Mark a task as blocked
+    Block(Select),
+    /// Mark a task as cancelled
+    Cancel(Select),
+    /// Mark a task as todo again
+    Reopen(Select),
+    /// Change the desc, times, due date, priority, tags or project of a task
+    Edit(Edit),
+    /// Move a task to another list, keeping its id and status
+    Move(Move),
+    /// Display the lists of tasks, or the tasks matching a filter such as status:todo +work
+    Display(Display),
+    /// Show the day, week and month lists and goals together
+    Agenda,
+    /// Carry unfinished tasks from the previous list into this one
+    Rollover,
+    /// Open an interactive interface, moving between dates with h, l and g
     Gui,
+    /// Print the effective configuration and where each value came from
+    Config,
+    /// Create, show, rename and delete named lists
+    #[command(subcommand)]
+    List(ListCommand),
+    /// Import every json list into a sqlite database and use it from then on
+    #[cfg(feature = "sqlite")]
+    Migrate,
 }
 
 #[derive(Args)]
 pub struct Add {
-    /// Desc of task must be unique to the list 
+    /// Desc of task
     pub desc: String,  
     /// Start time of the task if left blank will have no start time
     pub start_time: Option<String>,
     /// End time of the task if left blank will have no start time
     pub end_time: Option<String>,
+    /// Date the task is due, such as 2026-11-03, friday, +3d or end of month, with an optional time
+    #[arg(long, allow_hyphen_values = true)]
+    pub due: Option<String>,
+    /// How important the task is
+    #[arg(short, long, value_enum)]
+    pub priority: Option<Priority>,
+    /// Tag the task, can be given more than once. Words such as +work in the desc are tags too
+    #[arg(long = "tag", value_name = "TAG", value_parser = parse_tag)]
+    pub tags: Vec<String>,
+    /// Project the task belongs to, project:name in the desc does the same
+    #[arg(long, value_parser = parse_project)]
+    pub project: Option<String>,
 }
 
 #[derive(Args)]
 pub struct Delete {
-    /// Desc of task to delete 
-    pub desc: String,
+    /// Index, id, unique id prefix or desc of the task to delete
+    pub task: String,
 }
 
 #[derive(Args)]
 pub struct Complete {
-    /// Desc of task to 
-    pub desc: String, }
+    /// Index, id, unique id prefix or desc of the task to complete
+    pub task: String,
+}
+
+#[derive(Args)]
+pub struct Edit {
+    /// Index, id, unique id prefix or desc of the task to edit
+    pub task: String,
+    /// New desc of the task
+    #[arg(long)]
+    pub desc: Option<String>,
+    /// New start time of the task
+    #[arg(long, value_name = "HH:MM", conflicts_with = "clear_start")]
+    pub start: Option<String>,
+    /// New end time of the task
+    #[arg(long, value_name = "HH:MM", conflicts_with = "clear_end")]
+    pub end: Option<String>,
+    /// Remove the start time of the task
+    #[arg(long)]
+    pub clear_start: bool,
+    /// Remove the end time of the task
+    #[arg(long)]
+    pub clear_end: bool,
+    /// New due date of the task
+    #[arg(long, allow_hyphen_values = true, conflicts_with = "clear_due")]
+    pub due: Option<String>,
+    /// Remove the due date of the task
+    #[arg(long)]
+    pub clear_due: bool,
+    /// New priority of the task
+    #[arg(short, long, value_enum, conflicts_with = "clear_priority")]
+    pub priority: Option<Priority>,
+    /// Remove the priority of the task
+    #[arg(long)]
+    pub clear_priority: bool,
+    /// Add a tag to the task, can be given more than once
+    #[arg(long = "tag", value_name = "TAG", value_parser = parse_tag)]
+    pub tags: Vec<String>,
+    /// Remove a tag from the task, can be given more than once
+    #[arg(long, value_name = "TAG", value_parser = parse_tag)]
+    pub untag: Vec<String>,
+    /// New project of the task
+    #[arg(long, value_parser = parse_project, conflicts_with = "clear_project")]
+    pub project: Option<String>,
+    /// Remove the task from its project
+    #[arg(long)]
+    pub clear_project: bool,
+}
+
+#[derive(Args)]
+pub struct Display {
+    /// Only show tasks matching a filter, such as status:todo tag:work due.before:friday milk. Terms
+    /// can be joined with or, negated with not or - and grouped with parentheses. Put a filter
+    /// starting with - after --, as in -- -status:done
+    #[arg(value_name = "FILTER")]
+    pub filter: Vec<String>,
+    /// Show the matching tasks of every list instead of one
+    #[arg(long)]
+    pub all: bool,
+    /// Only show tasks with this tag, can be given more than once to need every tag
+    #[arg(long = "tag", value_name = "TAG", value_parser = parse_tag)]
+    pub tags: Vec<String>,
+    /// Only show tasks in this project
+    #[arg(long, value_parser = parse_project)]
+    pub project: Option<String>,
+    /// Write the tasks for scripts instead of people
+    #[arg(long, value_enum)]
+    pub format: Option<Format>,
+    /// Fields to write with --format, separated by commas. Every field when left out
+    #[arg(long, value_enum, value_delimiter = ',', requires = "format")]
+    pub fields: Vec<Column>,
+    /// Write a line for each task with fields filled in, such as '{id} {desc}'
+    #[arg(long, conflicts_with = "format")]
+    pub template: Option<String>,
+}
+
+#[derive(Args)]
+pub struct Move {
+    /// Index, id, unique id prefix or desc of the task to move
+    pub task: String,
+    /// List to move the task to
+    #[arg(long, value_name = "LIST")]
+    pub to: ListArg,
+    /// Date whose list to move the task to, the date of the list it is in when left out
+    #[arg(long, allow_hyphen_values = true, value_name = "DATE")]
+    pub to_date: Option<String>,
+}
+
+#[derive(Args)]
+pub struct Select {
+    /// Index, id, unique id prefix or desc of the task
+    pub task: String,
+}
+
+#[derive(Subcommand)]
+pub enum ListCommand {
+    /// Make a new empty named list
+    Create(ListName),
+    /// Show every named list and how many tasks it has
+    Ls,
+    /// Give a named list a new name
+    Rename(Rename),
+    /// Delete a named list and every task in it
+    Delete(ListName),
+}
+
+#[derive(Args)]
+pub struct ListName {
+    /// Name of the list
+    pub name: String,
+}
+
+#[derive(Args)]
+pub struct Rename {
+    /// Current name of the list
+    pub name: String,
+    /// New name of the list
+    pub new_name: String,
+}
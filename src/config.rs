@@ -0,0 +1,435 @@
+// User configuration read from a toml file at startup
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{NaiveTime, Weekday};
+use crossterm::event::KeyCode;
+use tui::style::Color;
+
+use crate::cl::{ListArg, Lists};
+use crate::error::PaskError;
+use crate::paths;
+use crate::tasks::{SortKey, Timing, DEFAULT_TIME_FORMAT};
+
+/// Environment variable that overrides the location of the config file.
+pub const CONFIG_VAR: &str = "PASK_CONFIG";
+
+/// Where the value of a setting came from.
+#[derive(Clone)]
+pub enum Source {
+    Default,
+    File(PathBuf),
+    Env(&'static str),
+    Flag(&'static str),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Env(var) => write!(f, "${}", var),
+            Source::Flag(flag) => write!(f, "{}", flag),
+        }
+    }
+}
+
+/// Colors used by the interactive interface.
+pub struct Colors {
+    /// Input box while typing a task
+    pub input: Color,
+    /// Input box in the other modes
+    pub input_inactive: Color,
+    /// Tasks that have not started yet
+    pub upcoming: Color,
+    /// Tasks that should be in progress now
+    pub in_progress: Color,
+    /// Unfinished tasks past their end time or due date
+    pub overdue: Color,
+    /// Done and cancelled tasks
+    pub done: Color,
+}
+
+impl Colors {
+    /// Gets the color tasks with the given timing are shown in.
+    pub fn timing(&self, timing: Timing) -> Color {
+        match timing {
+            Timing::Upcoming => self.upcoming,
+            Timing::InProgress => self.in_progress,
+            Timing::Overdue => self.overdue,
+            Timing::Done => self.done,
+        }
+    }
+}
+
+/// Keys used by the interactive interface.
+pub struct Keys {
+    pub quit: KeyCode,
+    pub insert: KeyCode,
+    pub edit: KeyCode,
+    /// Leaves insert or edit mode
+    pub back: KeyCode,
+    pub delete: KeyCode,
+    pub complete: KeyCode,
+    pub up: KeyCode,
+    pub down: KeyCode,
+    /// Opens the list before the current one
+    pub previous: KeyCode,
+    /// Opens the list after the current one
+    pub next: KeyCode,
+    /// Asks for a date to open the list of
+    pub goto: KeyCode,
+    /// Asks for a list to move the selected task to
+    pub move_to: KeyCode,
+    /// Changes the priority of the selected task to the next one
+    pub priority: KeyCode,
+    /// Asks for a filter of the tasks to show
+    pub filter: KeyCode,
+}
+
+/// Every setting pask reads from its config file.
+pub struct Config {
+    /// Day weekly lists start on
+    pub week_start: Weekday,
+    /// List used when none is given on the command line
+    pub default_list: ListArg,
+    /// strftime style format times are displayed with, `12h` and `24h` in the file are shorthands
+    pub time_format: String,
+    pub colors: Colors,
+    pub keys: Keys,
+    /// Whether unfinished tasks are carried into the list for the current period when it is used,
+    /// `auto` or `manual` in the file
+    pub auto_rollover: bool,
+    /// What lists are sorted by, `time`, `priority` or `due` in the file
+    pub sort: SortKey,
+    /// Directory lists are kept in, the platform data directory when not set
+    pub data_dir: Option<PathBuf>,
+    /// Config file that was looked for
+    pub path: Option<PathBuf>,
+    /// Where each setting that is not a default came from
+    sources: HashMap<&'static str, Source>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            week_start: Weekday::Sun,
+            default_list: ListArg::Kind(Lists::Day),
+            time_format: DEFAULT_TIME_FORMAT.to_owned(),
+            auto_rollover: true,
+            sort: SortKey::Time,
+            colors: Colors {
+                input: Color::LightBlue,
+                input_inactive: Color::Blue,
+                upcoming: Color::Reset,
+                in_progress: Color::Yellow,
+                overdue: Color::Red,
+                done: Color::DarkGray,
+            },
+            keys: Keys {
+                quit: KeyCode::Char('q'),
+                insert: KeyCode::Char('i'),
+                edit: KeyCode::Char('e'),
+                back: KeyCode::Esc,
+                delete: KeyCode::Char('d'),
+                complete: KeyCode::Enter,
+                up: KeyCode::Char('k'),
+                down: KeyCode::Char('j'),
+                previous: KeyCode::Char('h'),
+                next: KeyCode::Char('l'),
+                goto: KeyCode::Char('g'),
+                move_to: KeyCode::Char('m'),
+                priority: KeyCode::Char('p'),
+                filter: KeyCode::Char('f'),
+            },
+            data_dir: None,
+            path: None,
+            sources: HashMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config file, then applies the environment and the `--data-dir` flag on top.
+    ///
+    /// The file is `$PASK_CONFIG` or `pask/config.toml` in the platform config directory, a
+    /// missing file leaves every setting at its default.
+    pub fn load(data_dir_flag: Option<&Path>) -> Result<Self, PaskError> {
+        let path = std::env::var_os(CONFIG_VAR)
+            .filter(|x| !x.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| dirs::config_dir().map(|x| x.join("pask").join("config.toml")));
+        let mut config = Self { path, ..Self::default() };
+
+        if let Some(path) = config.path.clone() {
+            match fs::read_to_string(&path) {
+                Ok(content) => config.apply_file(&path, &content)?,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {},
+                Err(e) => return Err(PaskError::io(&path, e)),
+            }
+        }
+
+        if let Some(dir) = std::env::var_os(paths::HOME_VAR).filter(|x| !x.is_empty()) {
+            config.data_dir = Some(PathBuf::from(dir));
+            config.sources.insert("data_dir", Source::Env(paths::HOME_VAR));
+        }
+        if let Some(dir) = data_dir_flag {
+            config.data_dir = Some(dir.to_path_buf());
+            config.sources.insert("data_dir", Source::Flag("--data-dir"));
+        }
+
+        Ok(config)
+    }
+
+    /// Sets every value found in the toml content of a config file.
+    fn apply_file(&mut self, path: &Path, content: &str) -> Result<(), PaskError> {
+        let table: toml::value::Table = toml::from_str(content)
+            .map_err(|e| PaskError::parse_toml(path, e))?;
+
+        for (key, value) in flatten(&table) {
+            let invalid = |expected: &str| {
+                PaskError::Validation(format!("{}: {} should be {}", path.display(), key, expected))
+            };
+            let text = value.as_str().ok_or_else(|| invalid("a string"))?;
+
+            let name = *SETTINGS.iter().find(|x| **x == key).ok_or_else(|| {
+                PaskError::Validation(format!("{}: unknown setting {}", path.display(), key))
+            })?;
+
+            match name {
+                "week_start" => {
+                    self.week_start = text.parse().map_err(|_| invalid("a day of the week"))?;
+                },
+                "default_list" => {
+                    self.default_list = text.parse().map_err(|_| invalid("a list"))?;
+                },
+                "time_format" => {
+                    // 12h and 24h are shorthands for the usual clocks
+                    let text = match text {
+                        "12h" => "%-I:%M %p",
+                        "24h" => DEFAULT_TIME_FORMAT,
+                        x => x,
+                    };
+                    // Formats with dates in them, such as %d, are valid strftime but fail on times
+                    if write!(String::new(), "{}", NaiveTime::default().format(text)).is_err() {
+                        return Err(invalid("12h, 24h or a strftime time format"));
+                    }
+                    self.time_format = text.to_owned();
+                },
+                "rollover" => {
+                    self.auto_rollover = match text {
+                        "auto" => true,
+                        "manual" => false,
+                        _ => return Err(invalid("auto or manual")),
+                    };
+                },
+                "sort" => {
+                    self.sort = SortKey::from_name(text).ok_or_else(|| invalid("time, priority or due"))?;
+                },
+                "data_dir" => self.data_dir = Some(paths::expand_home(text)),
+                "colors.input" => {
+                    self.colors.input = parse_color(text).ok_or_else(|| invalid("a color"))?;
+                },
+                "colors.input_inactive" => {
+                    self.colors.input_inactive = parse_color(text).ok_or_else(|| invalid("a color"))?;
+                },
+                "colors.upcoming" => {
+                    self.colors.upcoming = parse_color(text).ok_or_else(|| invalid("a color"))?;
+                },
+                "colors.in_progress" => {
+                    self.colors.in_progress = parse_color(text).ok_or_else(|| invalid("a color"))?;
+                },
+                "colors.overdue" => {
+                    self.colors.overdue = parse_color(text).ok_or_else(|| invalid("a color"))?;
+                },
+                "colors.done" => {
+                    self.colors.done = parse_color(text).ok_or_else(|| invalid("a color"))?;
+                },
+                _ => {
+                    let keys = &mut self.keys;
+                    let target = match name {
+                        "keys.quit" => &mut keys.quit,
+                        "keys.insert" => &mut keys.insert,
+                        "keys.edit" => &mut keys.edit,
+                        "keys.back" => &mut keys.back,
+                        "keys.delete" => &mut keys.delete,
+                        "keys.complete" => &mut keys.complete,
+                        "keys.up" => &mut keys.up,
+                        "keys.down" => &mut keys.down,
+                        "keys.previous" => &mut keys.previous,
+                        "keys.next" => &mut keys.next,
+                        "keys.goto" => &mut keys.goto,
+                        "keys.move_to" => &mut keys.move_to,
+                        "keys.priority" => &mut keys.priority,
+                        _ => &mut keys.filter,
+                    };
+                    *target = parse_key(text).ok_or_else(|| invalid("a key"))?;
+                },
+            }
+            self.sources.insert(name, Source::File(path.to_path_buf()));
+        }
+
+        Ok(())
+    }
+
+    /// Lists every setting with its value formatted the way it is written in the config file.
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        let keys = &self.keys;
+        vec![
+            ("week_start", format!("{:?}", self.week_start).to_lowercase()),
+            ("default_list", self.default_list.to_string()),
+            ("time_format", self.time_format.clone()),
+            ("rollover", if self.auto_rollover { "auto" } else { "manual" }.to_owned()),
+            ("sort", self.sort.name().to_owned()),
+            ("data_dir", self.data_dir.clone().or_else(|| paths::default_data_dir().ok())
+                .map_or(String::new(), |x| x.display().to_string())),
+            ("colors.input", color_name(self.colors.input)),
+            ("colors.input_inactive", color_name(self.colors.input_inactive)),
+            ("colors.upcoming", color_name(self.colors.upcoming)),
+            ("colors.in_progress", color_name(self.colors.in_progress)),
+            ("colors.overdue", color_name(self.colors.overdue)),
+            ("colors.done", color_name(self.colors.done)),
+            ("keys.quit", key_name(keys.quit)),
+            ("keys.insert", key_name(keys.insert)),
+            ("keys.edit", key_name(keys.edit)),
+            ("keys.back", key_name(keys.back)),
+            ("keys.delete", key_name(keys.delete)),
+            ("keys.complete", key_name(keys.complete)),
+            ("keys.up", key_name(keys.up)),
+            ("keys.down", key_name(keys.down)),
+            ("keys.previous", key_name(keys.previous)),
+            ("keys.next", key_name(keys.next)),
+            ("keys.goto", key_name(keys.goto)),
+            ("keys.move_to", key_name(keys.move_to)),
+            ("keys.priority", key_name(keys.priority)),
+            ("keys.filter", key_name(keys.filter)),
+        ]
+    }
+
+    /// Gets where a setting came from.
+    pub fn source(&self, key: &str) -> Source {
+        self.sources.get(key).cloned().unwrap_or(Source::Default)
+    }
+}
+
+/// Name of every setting, nested settings are written with dots.
+const SETTINGS: [&str; 26] = [
+    "week_start",
+    "default_list",
+    "time_format",
+    "rollover",
+    "sort",
+    "data_dir",
+    "colors.input",
+    "colors.input_inactive",
+    "colors.upcoming",
+    "colors.in_progress",
+    "colors.overdue",
+    "colors.done",
+    "keys.quit",
+    "keys.insert",
+    "keys.edit",
+    "keys.back",
+    "keys.delete",
+    "keys.complete",
+    "keys.up",
+    "keys.down",
+    "keys.previous",
+    "keys.next",
+    "keys.goto",
+    "keys.move_to",
+    "keys.priority",
+    "keys.filter",
+];
+
+/// Turns nested tables into dotted keys, `[keys] quit = "x"` becomes `keys.quit`.
+fn flatten(table: &toml::value::Table) -> Vec<(String, &toml::Value)> {
+    let mut entries = vec![];
+    for (key, value) in table {
+        match value {
+            toml::Value::Table(inner) => {
+                for (inner_key, inner_value) in flatten(inner) {
+                    entries.push((format!("{}.{}", key, inner_key), inner_value));
+                }
+            },
+            _ => entries.push((key.clone(), value)),
+        }
+    }
+    entries
+}
+
+const COLORS: [(&str, Color); 17] = [
+    ("reset", Color::Reset),
+    ("black", Color::Black),
+    ("red", Color::Red),
+    ("green", Color::Green),
+    ("yellow", Color::Yellow),
+    ("blue", Color::Blue),
+    ("magenta", Color::Magenta),
+    ("cyan", Color::Cyan),
+    ("gray", Color::Gray),
+    ("darkgray", Color::DarkGray),
+    ("lightred", Color::LightRed),
+    ("lightgreen", Color::LightGreen),
+    ("lightyellow", Color::LightYellow),
+    ("lightblue", Color::LightBlue),
+    ("lightmagenta", Color::LightMagenta),
+    ("lightcyan", Color::LightCyan),
+    ("white", Color::White),
+];
+
+/// Parses a color name such as `lightblue` or a hex color such as `#ff8800`.
+pub fn parse_color(text: &str) -> Option<Color> {
+    let text = text.to_lowercase().replace(['_', '-', ' '], "");
+    if let Some(hex) = text.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+
+    COLORS.iter().find(|(name, _)| *name == text).map(|(_, color)| *color)
+}
+
+pub fn color_name(color: Color) -> String {
+    match color {
+        Color::Rgb(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        _ => COLORS.iter()
+            .find(|(_, x)| *x == color)
+            .map_or_else(|| format!("{:?}", color).to_lowercase(), |(name, _)| name.to_string()),
+    }
+}
+
+/// Parses a single character or the name of a special key such as `enter`.
+fn parse_key(text: &str) -> Option<KeyCode> {
+    let mut chars = text.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+
+    match text.to_lowercase().as_str() {
+        "enter" => Some(KeyCode::Enter),
+        "esc" | "escape" => Some(KeyCode::Esc),
+        "space" => Some(KeyCode::Char(' ')),
+        "tab" => Some(KeyCode::Tab),
+        "backspace" => Some(KeyCode::Backspace),
+        "delete" => Some(KeyCode::Delete),
+        "up" => Some(KeyCode::Up),
+        "down" => Some(KeyCode::Down),
+        "left" => Some(KeyCode::Left),
+        "right" => Some(KeyCode::Right),
+        _ => None,
+    }
+}
+
+fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => "space".to_owned(),
+        KeyCode::Char(c) => c.to_string(),
+        _ => format!("{:?}", key).to_lowercase(),
+    }
+}
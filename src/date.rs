@@ -0,0 +1,310 @@
+// Dates given on the command line and due dates of tasks
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{Datelike, Days, Duration, Months, NaiveDate, Weekday};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::cl::{ListArg, Lists};
+use crate::time::TimeOfDay;
+
+/// What relative dates are worked out from.
+#[derive(Copy, Clone)]
+pub struct DateContext {
+    pub today: NaiveDate,
+    pub week_start: Weekday,
+}
+
+/// Parses an absolute or relative date.
+///
+/// Understands `2026-11-03`, `today`, `tomorrow`, `yesterday`, weekday names such as `friday`
+/// (the next one, counting today), offsets such as `+3d`, `-1w`, `+2m`, `+1q` and `+1y`,
+/// `next week`, `last month` and `end of week`, `end of month`, `end of quarter` and `end of year`.
+pub fn parse_date(text: &str, context: DateContext) -> Result<NaiveDate, String> {
+    let today = context.today;
+    let lower = text.trim().to_lowercase();
+    let words: Vec<&str> = lower.split_whitespace().collect();
+
+    let date = match words.as_slice() {
+        ["today"] => Some(today),
+        ["tomorrow"] => today.succ_opt(),
+        ["yesterday"] => today.pred_opt(),
+        ["next", unit] => offset(today, 1, unit),
+        ["last", unit] => offset(today, -1, unit),
+        ["end", "of", unit] | [unit @ ("eow" | "eom" | "eoq" | "eoy")] => end_of(unit, context),
+        [word] => {
+            if let Ok(date) = NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+                Some(date)
+            } else if let Ok(weekday) = word.parse::<Weekday>() {
+                let ahead = (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+                Some(today + Duration::days(ahead.into()))
+            } else {
+                parse_offset(word).and_then(|(amount, unit)| offset(today, amount, unit))
+            }
+        },
+        _ => None,
+    };
+
+    date.ok_or_else(|| {
+        format!("{} is not a date, use YYYY-MM-DD, today, tomorrow, friday, +3d, -1w, next quarter or end of month", text)
+    })
+}
+
+/// Moves a date by a number of the periods a list covers, goals and named lists do not move.
+pub fn step(date: NaiveDate, list: &ListArg, amount: i64) -> NaiveDate {
+    let unit = match list {
+        ListArg::Kind(Lists::Goals) | ListArg::Named(_) => return date,
+        ListArg::Kind(Lists::Day) => "d",
+        ListArg::Kind(Lists::Week) => "w",
+        ListArg::Kind(Lists::Month) => "m",
+        ListArg::Kind(Lists::Quarter) => "q",
+        ListArg::Kind(Lists::Year) => "y",
+    };
+    offset(date, amount, unit).unwrap_or(date)
+}
+
+/// Splits an offset such as `+3d` into its amount and unit.
+fn parse_offset(word: &str) -> Option<(i64, &str)> {
+    let sign = match word.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let rest = &word[1..];
+    let split = rest.find(|c: char| !c.is_ascii_digit())?;
+    let amount: i64 = rest[..split].parse().ok()?;
+    Some((sign * amount, &rest[split..]))
+}
+
+/// Moves a date by a number of days, weeks, months or years.
+fn offset(date: NaiveDate, amount: i64, unit: &str) -> Option<NaiveDate> {
+    let days = |count: i64| -> Option<NaiveDate> {
+        // chrono panics on day counts too big for a Duration, none of which land on a date anyway
+        let range = NaiveDate::MAX.signed_duration_since(NaiveDate::MIN).num_days();
+        if count.abs() > range {
+            return None;
+        }
+        let count = Days::new(count.unsigned_abs());
+        if amount < 0 {
+            date.checked_sub_days(count)
+        } else {
+            date.checked_add_days(count)
+        }
+    };
+    let months = |count: i64| -> Option<NaiveDate> {
+        let count = Months::new(u32::try_from(count.abs()).ok()?);
+        if amount < 0 {
+            date.checked_sub_months(count)
+        } else {
+            date.checked_add_months(count)
+        }
+    };
+
+    match unit {
+        "d" | "day" | "days" => days(amount),
+        "w" | "week" | "weeks" => days(amount.checked_mul(7)?),
+        "m" | "month" | "months" => months(amount),
+        "q" | "quarter" | "quarters" => months(amount.checked_mul(3)?),
+        "y" | "year" | "years" => months(amount.checked_mul(12)?),
+        _ => None,
+    }
+}
+
+/// Gets the last day of the week, month, quarter or year today is in.
+fn end_of(unit: &str, context: DateContext) -> Option<NaiveDate> {
+    let today = context.today;
+    match unit {
+        "week" | "eow" => Some(today.week(context.week_start).last_day()),
+        "month" | "eom" => {
+            let first = NaiveDate::from_ymd_opt(today.year(), today.month(), 1)?;
+            first.checked_add_months(Months::new(1))?.pred_opt()
+        },
+        "quarter" | "eoq" => {
+            let first = NaiveDate::from_ymd_opt(today.year(), today.month0() / 3 * 3 + 1, 1)?;
+            first.checked_add_months(Months::new(3))?.pred_opt()
+        },
+        "year" | "eoy" => NaiveDate::from_ymd_opt(today.year(), 12, 31),
+        _ => None,
+    }
+}
+
+/// When a task has to be done by, a date and optionally a time on it.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Due {
+    pub date: NaiveDate,
+    pub time: Option<TimeOfDay>,
+}
+
+impl Due {
+    /// Parses a date as in `parse_date`, optionally followed by a time such as `friday 5pm`.
+    pub fn parse(text: &str, context: DateContext) -> Result<Self, String> {
+        let error = match parse_date(text, context) {
+            Ok(date) => return Ok(Self { date, time: None }),
+            Err(e) => e,
+        };
+
+        // Try the last word as the time
+        let (date, time) = text.trim().rsplit_once(' ').ok_or_else(|| error.clone())?;
+        let time: TimeOfDay = time.parse().map_err(|_| error)?;
+        Ok(Self { date: parse_date(date, context)?, time: Some(time) })
+    }
+
+    /// Formats the due date, with its time in a strftime style format.
+    pub fn format(self, time_format: &str) -> String {
+        match self.time {
+            Some(time) => format!("{} {}", self.date.format("%Y-%m-%d"), time.format(time_format)),
+            None => self.date.format("%Y-%m-%d").to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Due {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.time {
+            Some(time) => write!(f, "{} {}", self.date.format("%Y-%m-%d"), time),
+            None => write!(f, "{}", self.date.format("%Y-%m-%d")),
+        }
+    }
+}
+
+/// Parses the saved `YYYY-MM-DD` or `YYYY-MM-DD HH:MM` form.
+impl FromStr for Due {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (date, time) = match text.split_once(' ') {
+            Some((date, time)) => (date, Some(time.parse()?)),
+            None => (text, None),
+        };
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| format!("{}: {}", text, e))?;
+        Ok(Self { date, time })
+    }
+}
+
+impl Serialize for Due {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Due {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    /// Sunday 2026-10-18, with weeks starting on Sunday.
+    fn context() -> DateContext {
+        DateContext { today: date(2026, 10, 18), week_start: Weekday::Sun }
+    }
+
+    fn on(today: NaiveDate) -> DateContext {
+        DateContext { today, ..context() }
+    }
+
+    #[test]
+    fn dates_parse_relative_to_today() {
+        let cases = [
+            ("2026-11-03", date(2026, 11, 3)),
+            ("today", date(2026, 10, 18)),
+            ("tomorrow", date(2026, 10, 19)),
+            ("yesterday", date(2026, 10, 17)),
+            ("sunday", date(2026, 10, 18)),
+            ("monday", date(2026, 10, 19)),
+            (" Friday ", date(2026, 10, 23)),
+            ("sat", date(2026, 10, 24)),
+            ("+3d", date(2026, 10, 21)),
+            ("-1w", date(2026, 10, 11)),
+            ("+2m", date(2026, 12, 18)),
+            ("+1q", date(2027, 1, 18)),
+            ("+1y", date(2027, 10, 18)),
+            ("+10days", date(2026, 10, 28)),
+            ("next week", date(2026, 10, 25)),
+            ("last month", date(2026, 9, 18)),
+            ("next quarter", date(2027, 1, 18)),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse_date(text, context()), Ok(expected), "{}", text);
+        }
+    }
+
+    #[test]
+    fn months_clamp_to_their_last_day() {
+        assert_eq!(parse_date("+1m", on(date(2026, 1, 31))), Ok(date(2026, 2, 28)));
+        assert_eq!(parse_date("+1m", on(date(2028, 1, 31))), Ok(date(2028, 2, 29)));
+        assert_eq!(parse_date("last month", on(date(2026, 3, 31))), Ok(date(2026, 2, 28)));
+        assert_eq!(parse_date("next quarter", on(date(2026, 11, 30))), Ok(date(2027, 2, 28)));
+        assert_eq!(parse_date("+1y", on(date(2028, 2, 29))), Ok(date(2029, 2, 28)));
+    }
+
+    #[test]
+    fn ends_of_periods() {
+        let cases = [
+            ("end of week", date(2026, 10, 24)),
+            ("eow", date(2026, 10, 24)),
+            ("end of month", date(2026, 10, 31)),
+            ("eom", date(2026, 10, 31)),
+            ("end of quarter", date(2026, 12, 31)),
+            ("eoy", date(2026, 12, 31)),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse_date(text, context()), Ok(expected), "{}", text);
+        }
+
+        let monday_weeks = DateContext { week_start: Weekday::Mon, ..context() };
+        assert_eq!(parse_date("end of week", monday_weeks), Ok(date(2026, 10, 18)));
+        assert_eq!(parse_date("end of month", on(date(2028, 2, 3))), Ok(date(2028, 2, 29)));
+        assert_eq!(parse_date("end of quarter", on(date(2026, 2, 10))), Ok(date(2026, 3, 31)));
+        assert_eq!(parse_date("eoq", on(date(2026, 4, 1))), Ok(date(2026, 6, 30)));
+    }
+
+    #[test]
+    fn bad_dates_are_rejected() {
+        let too_far = ["+200000000d", "+99999999999999d", "-99999999999999999d", "+9999999999999999999w", "+99999999999999w", "+4294967296m"];
+        for text in ["", "someday", "2026-02-30", "+3", "+d", "+3x", "3d", "next fortnight", "end of decade", "friday week"]
+            .into_iter()
+            .chain(too_far)
+        {
+            assert!(parse_date(text, context()).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn due_dates_can_have_a_time() {
+        let due = |date, time: Option<(u8, u8)>| Due { date, time: time.and_then(|(h, m)| TimeOfDay::new(h, m)) };
+
+        assert_eq!(Due::parse("friday", context()), Ok(due(date(2026, 10, 23), None)));
+        assert_eq!(Due::parse("friday 5pm", context()), Ok(due(date(2026, 10, 23), Some((17, 0)))));
+        assert_eq!(Due::parse("next week 9:30", context()), Ok(due(date(2026, 10, 25), Some((9, 30)))));
+        assert_eq!(Due::parse("2026-11-03 14h30", context()), Ok(due(date(2026, 11, 3), Some((14, 30)))));
+        for text in ["5pm", "friday 25pm", "someday 5pm"] {
+            assert!(Due::parse(text, context()).is_err(), "{}", text);
+        }
+
+        let saved = due(date(2026, 10, 23), Some((17, 0)));
+        assert_eq!(saved.to_string(), "2026-10-23 17:00");
+        assert_eq!("2026-10-23 17:00".parse(), Ok(saved));
+        assert_eq!("2026-10-23".parse(), Ok(due(date(2026, 10, 23), None)));
+    }
+
+    #[test]
+    fn steps_move_by_the_period_of_a_list() {
+        let today = date(2026, 3, 31);
+        assert_eq!(step(today, &ListArg::Kind(Lists::Day), 1), date(2026, 4, 1));
+        assert_eq!(step(today, &ListArg::Kind(Lists::Week), -1), date(2026, 3, 24));
+        assert_eq!(step(today, &ListArg::Kind(Lists::Month), -1), date(2026, 2, 28));
+        assert_eq!(step(today, &ListArg::Kind(Lists::Quarter), 1), date(2026, 6, 30));
+        assert_eq!(step(today, &ListArg::Kind(Lists::Year), 2), date(2028, 3, 31));
+        assert_eq!(step(today, &ListArg::Kind(Lists::Goals), 1), today);
+        assert_eq!(step(today, &ListArg::Named("trip".to_owned()), 1), today);
+    }
+}
@@ -0,0 +1,103 @@
+// Errors that can stop a pask command from completing
+use std::path::{Path, PathBuf};
+use std::{fmt, io};
+
+/// Everything that can go wrong while running pask.
+#[derive(Debug)]
+pub enum PaskError {
+    /// Reading or writing a file failed
+    Io { path: Option<PathBuf>, source: io::Error },
+    /// A list file is not valid json or does not hold a list of tasks
+    Parse { path: PathBuf, line: usize, column: usize, message: String },
+    /// A list file was written by a newer version of pask
+    Version { path: PathBuf, found: u64, supported: u64 },
+    /// The user gave input that does not make sense
+    Validation(String),
+    /// Another pask process is changing the list
+    Locked(String),
+    /// The sqlite database returned an error
+    #[cfg(feature = "sqlite")]
+    Database { path: PathBuf, source: rusqlite::Error },
+}
+
+impl PaskError {
+    /// Creates an io error that happened on the given file.
+    pub fn io(path: &Path, source: io::Error) -> Self {
+        Self::Io { path: Some(path.to_path_buf()), source }
+    }
+
+    /// Creates a parse error from a serde error that happened in the given file.
+    pub fn parse(path: &Path, source: serde_json::Error) -> Self {
+        // serde puts the position at the end of the message, it is kept separately instead
+        let message = source.to_string();
+        let position = format!(" at line {} column {}", source.line(), source.column());
+        let message = message.strip_suffix(&position).unwrap_or(&message).to_owned();
+
+        Self::Parse { path: path.to_path_buf(), line: source.line(), column: source.column(), message }
+    }
+
+    /// Creates a parse error from a toml error that happened in the given file.
+    pub fn parse_toml(path: &Path, source: toml::de::Error) -> Self {
+        let (line, column) = source.line_col().map_or((0, 0), |(l, c)| (l + 1, c + 1));
+        let message = source.to_string();
+        let position = format!(" at line {} column {}", line, column);
+        let message = message.strip_suffix(&position).unwrap_or(&message).to_owned();
+
+        Self::Parse { path: path.to_path_buf(), line, column, message }
+    }
+
+    /// Code the process exits with so scripts can tell the errors apart. 2 is left to clap for
+    /// arguments it could not parse.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Io { .. } => 3,
+            Self::Parse { .. } | Self::Version { .. } => 4,
+            Self::Locked(_) => 5,
+            #[cfg(feature = "sqlite")]
+            Self::Database { .. } => 6,
+            Self::Validation(_) => 7,
+        }
+    }
+}
+
+impl fmt::Display for PaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path: Some(path), source } => write!(f, "{}: {}", path.display(), source),
+            Self::Io { path: None, source } => write!(f, "{}", source),
+            Self::Parse { path, line: 0, message, .. } => write!(f, "{}: {}", path.display(), message),
+            Self::Parse { path, line, column, message } => {
+                write!(f, "{}:{}:{}: {}", path.display(), line, column, message)
+            },
+            Self::Version { path, found, supported } => write!(
+                f,
+                "{}: written in format version {} but this pask only understands up to {}",
+                path.display(), found, supported
+            ),
+            Self::Validation(message) => write!(f, "{}", message),
+            Self::Locked(name) => {
+                write!(f, "the list {} is locked by another pask process, close it and try again", name)
+            },
+            #[cfg(feature = "sqlite")]
+            Self::Database { path, source } => write!(f, "{}: {}", path.display(), source),
+        }
+    }
+}
+
+impl std::error::Error for PaskError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            #[cfg(feature = "sqlite")]
+            Self::Database { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Errors from the terminal have no file to blame.
+impl From<io::Error> for PaskError {
+    fn from(source: io::Error) -> Self {
+        Self::Io { path: None, source }
+    }
+}
@@ -0,0 +1,430 @@
+// Filter expressions such as `status:todo tag:work due.before:friday "text search"`, parsed
+// into a tree and matched against tasks
+use std::fmt;
+
+use chrono::NaiveDate;
+use clap::ValueEnum;
+
+use crate::date::{self, DateContext};
+use crate::tasks::{Priority, Status, Task};
+
+/// Tasks to show, written in the filter language. An empty filter matches every task.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Filter {
+    /// The filter as it was written
+    text: String,
+    expr: Option<Expr>,
+}
+
+impl Filter {
+    /// Parses a filter, relative dates are worked out from `context`.
+    ///
+    /// Terms next to each other must all match, `or` matches either side and `not` or a leading
+    /// `-` matches tasks the term does not. `and` binds tighter than `or`, and parentheses group.
+    pub fn parse(text: &str, context: DateContext) -> Result<Self, String> {
+        let mut parser = Parser { tokens: tokenize(text)?, pos: 0, context };
+        let expr = if parser.tokens.is_empty() { None } else { Some(parser.or()?) };
+        if parser.pos < parser.tokens.len() {
+            return Err("There is a ) without a ( before it in the filter".to_owned());
+        }
+        Ok(Self { text: text.trim().to_owned(), expr })
+    }
+
+    /// Whether the filter lets every task through.
+    pub fn is_empty(&self) -> bool {
+        self.expr.is_none()
+    }
+
+    /// Whether a task matches the filter.
+    pub fn matches(&self, task: &Task) -> bool {
+        self.expr.as_ref().is_none_or(|x| x.matches(task))
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+/// A parsed filter.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Expr {
+    /// The desc contains the text, ignoring case. Kept in lowercase
+    Text(String),
+    Status(Status),
+    Tag(String),
+    Project(String),
+    Priority(Priority),
+    /// The id starts with the text
+    Id(String),
+    /// The task is due on, before or after a date
+    Due(Compare, NaiveDate),
+    /// The task has a value for the field
+    Has(Field),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+/// How a date in a filter is compared.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Compare {
+    On,
+    Before,
+    After,
+}
+
+/// Fields that can be checked with `has:`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Field {
+    Due,
+    Priority,
+    Project,
+    Tags,
+    /// A start time
+    Time,
+}
+
+impl Expr {
+    /// Evaluates the expression for a task.
+    pub fn matches(&self, task: &Task) -> bool {
+        match self {
+            Expr::Text(text) => task.desc.to_lowercase().contains(text),
+            Expr::Status(status) => task.status == *status,
+            Expr::Tag(tag) => task.tags.contains(tag),
+            Expr::Project(project) => task.project.as_ref() == Some(project),
+            Expr::Priority(priority) => task.priority == Some(*priority),
+            Expr::Id(prefix) => task.id.starts_with(prefix.as_str()),
+            Expr::Due(compare, date) => task.due.is_some_and(|due| match compare {
+                Compare::On => due.date == *date,
+                Compare::Before => due.date < *date,
+                Compare::After => due.date > *date,
+            }),
+            Expr::Has(field) => match field {
+                Field::Due => task.due.is_some(),
+                Field::Priority => task.priority.is_some(),
+                Field::Project => task.project.is_some(),
+                Field::Tags => !task.tags.is_empty(),
+                Field::Time => task.start_time.is_some(),
+            },
+            Expr::Not(expr) => !expr.matches(task),
+            Expr::And(a, b) => a.matches(task) && b.matches(task),
+            Expr::Or(a, b) => a.matches(task) || b.matches(task),
+        }
+    }
+}
+
+/// Pieces a filter is split into before it is parsed.
+#[derive(PartialEq, Eq, Debug)]
+enum Token {
+    Open,
+    Close,
+    Word(String),
+    /// Text that started with a quote, always searched for as text
+    Quoted(String),
+}
+
+/// Splits a filter into words and parentheses. Quotes keep spaces and parentheses inside a word,
+/// as in `"text search"` or `due.before:"end of month"`.
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c == '(' || c == ')' {
+            chars.next();
+            tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            continue;
+        }
+
+        let quoted = c == '"';
+        let mut word = String::new();
+        let mut in_quotes = false;
+        while let Some(&c) = chars.peek() {
+            if !in_quotes && (c.is_whitespace() || c == '(' || c == ')') {
+                break;
+            }
+            chars.next();
+            match c {
+                '"' => in_quotes = !in_quotes,
+                _ => word.push(c),
+            }
+        }
+        if in_quotes {
+            return Err("A quote in the filter is never closed".to_owned());
+        }
+        tokens.push(if quoted { Token::Quoted(word) } else { Token::Word(word) });
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser over the tokens of a filter.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    context: DateContext,
+}
+
+impl Parser {
+    /// Skips the next token if it is the given keyword.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = matches!(self.tokens.get(self.pos), Some(Token::Word(x)) if x == keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    /// `and ("or" and)*`
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    /// `unary (["and"] unary)*`, stopping at `or` or a closing parenthesis
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        loop {
+            match self.tokens.get(self.pos) {
+                None | Some(Token::Close) => break,
+                Some(Token::Word(x)) if x == "or" => break,
+                _ => {},
+            }
+            self.keyword("and");
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    /// `"not" unary | "(" or ")" | term`
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.keyword("not") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        match token {
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                if self.tokens.get(self.pos) != Some(&Token::Close) {
+                    return Err("There is a ( without a ) after it in the filter".to_owned());
+                }
+                self.pos += 1;
+                Ok(expr)
+            },
+            Some(Token::Close) => Err("There is a ) without a ( before it in the filter".to_owned()),
+            Some(Token::Quoted(text)) => Ok(Expr::Text(text.to_lowercase())),
+            Some(Token::Word(word)) => term(word, self.context),
+            None => Err("The filter ends where a term was expected".to_owned()),
+        }
+    }
+}
+
+/// Parses a single word such as `status:done`, `+work`, `-tag:home` or `milk`.
+fn term(word: &str, context: DateContext) -> Result<Expr, String> {
+    if let Some(rest) = word.strip_prefix('-').filter(|x| !x.is_empty()) {
+        return Ok(Expr::Not(Box::new(term(rest, context)?)));
+    }
+    if let Some(tag) = word.strip_prefix('+') {
+        return Ok(Expr::Tag(parse_tag(tag)?));
+    }
+
+    // Words such as 10:30 are searched for as text, only lowercase names are fields
+    let (field, value) = match word.split_once(':') {
+        Some((field, value)) if !field.is_empty() && field.chars().all(|c| c.is_ascii_lowercase() || c == '.') => {
+            (field, value)
+        },
+        _ => return Ok(Expr::Text(word.to_lowercase())),
+    };
+
+    let due = |compare| date::parse_date(value, context).map(|x| Expr::Due(compare, x));
+    match field {
+        "status" => Status::from_name(value).map(Expr::Status).ok_or_else(|| format!(
+            "{} is not a status, use todo, in-progress, blocked, done, cancelled or carried-over", value
+        )),
+        "tag" => Ok(Expr::Tag(parse_tag(value)?)),
+        "project" => Ok(Expr::Project(parse_project(value)?)),
+        "priority" => Priority::from_str(value, true)
+            .map(Expr::Priority)
+            .map_err(|_| format!("{} is not a priority, use high, medium or low", value)),
+        "id" if !value.is_empty() => Ok(Expr::Id(value.to_owned())),
+        "id" => Err("id: needs the start of an id".to_owned()),
+        "due" => due(Compare::On),
+        "due.before" => due(Compare::Before),
+        "due.after" => due(Compare::After),
+        "has" => match value {
+            "due" => Ok(Expr::Has(Field::Due)),
+            "priority" => Ok(Expr::Has(Field::Priority)),
+            "project" => Ok(Expr::Has(Field::Project)),
+            "tag" | "tags" => Ok(Expr::Has(Field::Tags)),
+            "time" => Ok(Expr::Has(Field::Time)),
+            _ => Err(format!("has:{} is not a field, use has:due, has:priority, has:project, has:tag or has:time", value)),
+        },
+        _ => Err(format!(
+            "{} is not a field, use status, tag, project, priority, id, due, due.before, due.after or has", field
+        )),
+    }
+}
+
+/// A tag or project written in a desc.
+pub enum Label {
+    Tag(String),
+    Project(String),
+}
+
+impl Label {
+    /// Parses a word such as `+work` or `project:home`.
+    pub fn parse(word: &str) -> Option<Self> {
+        if let Some(tag) = word.strip_prefix('+') {
+            return check_label(tag).ok().map(|_| Label::Tag(tag.to_owned()));
+        }
+        let project = word.strip_prefix("project:")?;
+        check_label(project).ok().map(|_| Label::Project(project.to_owned()))
+    }
+}
+
+/// Makes sure a tag or project name is one word that can be written back in a desc.
+fn check_label(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.starts_with('+') || name.contains(char::is_whitespace) {
+        return Err(format!("{:?} is not a valid tag or project, they are single words", name));
+    }
+    Ok(())
+}
+
+/// Parses a tag given on the command line, with or without its `+`.
+pub fn parse_tag(text: &str) -> Result<String, String> {
+    let tag = text.strip_prefix('+').unwrap_or(text);
+    check_label(tag)?;
+    Ok(tag.to_owned())
+}
+
+/// Parses a project given on the command line.
+pub fn parse_project(text: &str) -> Result<String, String> {
+    check_label(text)?;
+    Ok(text.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Weekday;
+
+    use super::*;
+    use crate::date::Due;
+
+    fn context() -> DateContext {
+        DateContext { today: date(2026, 10, 18), week_start: Weekday::Sun }
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn parse(text: &str) -> Expr {
+        Filter::parse(text, context()).unwrap().expr.unwrap()
+    }
+
+    fn and(a: Expr, b: Expr) -> Expr {
+        Expr::And(Box::new(a), Box::new(b))
+    }
+
+    fn or(a: Expr, b: Expr) -> Expr {
+        Expr::Or(Box::new(a), Box::new(b))
+    }
+
+    fn not(a: Expr) -> Expr {
+        Expr::Not(Box::new(a))
+    }
+
+    fn tag(name: &str) -> Expr {
+        Expr::Tag(name.to_owned())
+    }
+
+    #[test]
+    fn tokens_keep_quoted_text_together() {
+        let words = |x: &[&str]| x.iter().map(|x| Token::Word(x.to_string())).collect::<Vec<_>>();
+        assert_eq!(tokenize("  a  b ").unwrap(), words(&["a", "b"]));
+        assert_eq!(tokenize("due.before:\"end of month\"").unwrap(), words(&["due.before:end of month"]));
+        assert_eq!(
+            tokenize("(a \"b (c)\")").unwrap(),
+            vec![Token::Open, Token::Word("a".to_owned()), Token::Quoted("b (c)".to_owned()), Token::Close],
+        );
+        assert!(tokenize("\"open").is_err());
+    }
+
+    #[test]
+    fn terms_parse_to_fields() {
+        assert_eq!(parse("status:todo"), Expr::Status(Status::Todo));
+        assert_eq!(parse("status:carried-over"), Expr::Status(Status::CarriedOver));
+        assert_eq!(parse("tag:work"), tag("work"));
+        assert_eq!(parse("+work"), tag("work"));
+        assert_eq!(parse("project:home"), Expr::Project("home".to_owned()));
+        assert_eq!(parse("priority:h"), Expr::Priority(Priority::High));
+        assert_eq!(parse("id:4e0b"), Expr::Id("4e0b".to_owned()));
+        assert_eq!(parse("due:2026-11-03"), Expr::Due(Compare::On, date(2026, 11, 3)));
+        assert_eq!(parse("due.before:friday"), Expr::Due(Compare::Before, date(2026, 10, 23)));
+        assert_eq!(parse("due.after:\"end of month\""), Expr::Due(Compare::After, date(2026, 10, 31)));
+        assert_eq!(parse("has:time"), Expr::Has(Field::Time));
+        assert_eq!(parse("Milk"), Expr::Text("milk".to_owned()));
+        assert_eq!(parse("\"status:todo\""), Expr::Text("status:todo".to_owned()));
+        assert_eq!(parse("10:30"), Expr::Text("10:30".to_owned()));
+        assert_eq!(parse("-tag:home"), not(tag("home")));
+    }
+
+    #[test]
+    fn operators_nest_by_precedence() {
+        assert_eq!(parse("+a +b"), and(tag("a"), tag("b")));
+        assert_eq!(parse("+a and +b"), and(tag("a"), tag("b")));
+        assert_eq!(parse("+a or +b +c"), or(tag("a"), and(tag("b"), tag("c"))));
+        assert_eq!(parse("(+a or +b) +c"), and(or(tag("a"), tag("b")), tag("c")));
+        assert_eq!(parse("not +a or +b"), or(not(tag("a")), tag("b")));
+        assert_eq!(parse("not not +a"), not(not(tag("a"))));
+        assert_eq!(Filter::parse("  ", context()).unwrap(), Filter::default());
+    }
+
+    #[test]
+    fn bad_filters_are_rejected() {
+        for text in [
+            "status:later", "priority:urgent", "colour:red", "has:colour", "due.before:someday", "tag:",
+            "id:", "(+a", "+a)", ")", "+a or", "not", "\"open",
+        ] {
+            assert!(Filter::parse(text, context()).is_err(), "{} should not parse", text);
+        }
+    }
+
+    #[test]
+    fn expressions_match_tasks() {
+        let mut task = Task::new();
+        task.desc = "Buy Milk".to_owned();
+        task.status = Status::InProgress;
+        task.tags = vec!["errands".to_owned(), "home".to_owned()];
+        task.project = Some("house".to_owned());
+        task.priority = Some(Priority::Low);
+        task.due = Some(Due { date: date(2026, 10, 21), time: None });
+
+        let matches = |text: &str| Filter::parse(text, context()).unwrap().matches(&task);
+        for text in [
+            "", "milk", "\"buy milk\"", "status:in-progress", "+errands +home", "project:house",
+            "priority:low", "due:wednesday", "due.before:friday", "due.after:today", "has:due",
+            "has:tags", "-has:time", "status:done or +home", "not (+work or project:garden)",
+        ] {
+            assert!(matches(text), "{} should match", text);
+        }
+        for text in [
+            "eggs", "status:todo", "+work", "+errands +work", "project:garden", "priority:high",
+            "due:today", "due.before:wednesday", "due.after:\"end of month\"", "has:time",
+            "-milk", "status:done or +work", "not (+work or project:house)",
+        ] {
+            assert!(!matches(text), "{} should not match", text);
+        }
+        let id = task.id[..6].to_owned();
+        assert!(matches(&format!("id:{}", id)));
+    }
+}
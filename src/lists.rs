@@ -0,0 +1,141 @@
+// Creating, showing, renaming and deleting named lists
+use chrono::{NaiveDate, Weekday};
+use clap::CommandFactory;
+
+use crate::cl::{check_list_name, Cli, ListArg, ListCommand};
+use crate::error::PaskError;
+use crate::period::Period;
+use crate::storage::Storage;
+use crate::tasks::Tasks;
+
+/// Runs one of the `pask list` commands.
+pub fn run(command: ListCommand, storage: &dyn Storage) -> Result<(), PaskError> {
+    match command {
+        ListCommand::Create(x) => {
+            let key = new_key(storage, &x.name)?;
+            let _lock = storage.lock(&key)?;
+            storage.save(&key, &Tasks { tasks: vec![] })?;
+            println!("Created list {}", x.name);
+        },
+        ListCommand::Ls => {
+            let names = named_lists(storage)?;
+            if names.is_empty() {
+                println!("No named lists, make one with pask list create <name>");
+            }
+            let width = names.iter().map(String::len).max().unwrap_or(0);
+            for name in names {
+                let tasks = storage.load(&Period::Named(name.clone()).key())?.tasks;
+                let unfinished = tasks.iter().filter(|x| x.is_unfinished()).count();
+                println!("{:<width$}  {} tasks, {} unfinished", name, tasks.len(), unfinished, width = width);
+            }
+        },
+        ListCommand::Rename(x) => {
+            let key = existing_key(storage, &x.name)?;
+            let new_key = new_key(storage, &x.new_name)?;
+            let _lock = storage.lock(&key)?;
+            let _new_lock = storage.lock(&new_key)?;
+
+            // Only remove the old list once its tasks are safely under the new name
+            storage.save(&new_key, &storage.load(&key)?)?;
+            storage.delete(&key)?;
+            println!("Renamed list {} to {}", x.name, x.new_name);
+        },
+        ListCommand::Delete(x) => {
+            let key = existing_key(storage, &x.name)?;
+            let _lock = storage.lock(&key)?;
+            let count = storage.load(&key)?.tasks.len();
+            storage.delete(&key)?;
+            println!("Deleted list {} and its {} tasks", x.name, count);
+        },
+    }
+    Ok(())
+}
+
+/// Gets the names of every named list, sorted.
+pub fn named_lists(storage: &dyn Storage) -> Result<Vec<String>, PaskError> {
+    let mut names: Vec<String> = storage.lists()?.into_iter()
+        .filter_map(|x| match Period::from_key(&x) {
+            Some(Period::Named(name)) => Some(name),
+            _ => None,
+        })
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Gets the key of the list covering `date`, failing for named lists that have not been made so a
+/// typo does not quietly start a new list.
+pub fn key(storage: &dyn Storage, list: &ListArg, date: NaiveDate, week_start: Weekday) -> Result<String, PaskError> {
+    match list {
+        ListArg::Named(name) => existing_key(storage, name),
+        ListArg::Kind(_) => Ok(Period::new(list, date, week_start).key()),
+    }
+}
+
+/// Gets the key of a named list, failing if it has not been made.
+fn existing_key(storage: &dyn Storage, name: &str) -> Result<String, PaskError> {
+    if !named_lists(storage)?.iter().any(|x| x == name) {
+        return Err(PaskError::Validation(format!(
+            "There is no list named {}, make it with pask list create {}", name, name
+        )));
+    }
+    Ok(Period::Named(name.to_owned()).key())
+}
+
+/// Gets the key for a new named list, failing if the name is taken or could not be told apart
+/// from a pask command or built in list on the command line.
+fn new_key(storage: &dyn Storage, name: &str) -> Result<String, PaskError> {
+    check_list_name(name).map_err(PaskError::Validation)?;
+    if let Ok(ListArg::Kind(_)) = name.parse() {
+        return Err(PaskError::Validation(format!("{} is a built in list", name)));
+    }
+    // Building the command adds the help subcommand clap makes for it
+    let mut command = Cli::command();
+    command.build();
+    if command.get_subcommands().any(|x| x.get_name() == name) {
+        return Err(PaskError::Validation(format!("{} is a pask command and can not be a list name", name)));
+    }
+    if named_lists(storage)?.iter().any(|x| x == name) {
+        return Err(PaskError::Validation(format!("There is already a list named {}", name)));
+    }
+    Ok(Period::Named(name.to_owned()).key())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cl::{ListName, Rename};
+    use crate::storage::Memory;
+    use crate::tasks::Task;
+
+    fn name(name: &str) -> ListName {
+        ListName { name: name.to_owned() }
+    }
+
+    #[test]
+    fn named_lists_keep_their_tasks_when_renamed() {
+        let storage = Memory::default();
+        run(ListCommand::Create(name("trip")), &storage).unwrap();
+        storage.save("trip-list", &Tasks { tasks: vec![Task { desc: "Pack".to_owned(), ..Task::new() }] }).unwrap();
+
+        let rename = |from: &str, to: &str| Rename { name: from.to_owned(), new_name: to.to_owned() };
+        run(ListCommand::Rename(rename("trip", "holiday")), &storage).unwrap();
+        assert_eq!(named_lists(&storage).unwrap(), ["holiday"]);
+        assert_eq!(storage.load("holiday-list").unwrap().tasks[0].desc, "Pack");
+
+        // Renaming onto a list that exists or from one that does not changes nothing
+        run(ListCommand::Create(name("work")), &storage).unwrap();
+        assert!(run(ListCommand::Rename(rename("holiday", "work")), &storage).is_err());
+        assert!(run(ListCommand::Rename(rename("trip", "other")), &storage).is_err());
+        assert_eq!(named_lists(&storage).unwrap(), ["holiday", "work"]);
+        assert_eq!(storage.load("holiday-list").unwrap().tasks.len(), 1);
+
+        for taken in ["day", "add", "help"] {
+            assert!(run(ListCommand::Create(name(taken)), &storage).is_err(), "{}", taken);
+        }
+
+        run(ListCommand::Delete(name("holiday")), &storage).unwrap();
+        assert!(run(ListCommand::Delete(name("holiday")), &storage).is_err());
+        assert_eq!(storage.lists().unwrap(), ["work-list"]);
+    }
+}
@@ -1,100 +1,361 @@
-use std::fs;
-use std::env;
+use std::io::{self, IsTerminal};
+use std::process;
 
 use clap::Parser;
 use chrono::prelude::*;
-use dirs::home_dir;
+use crossterm::style::Stylize;
 
 mod cl;
+mod config;
+mod date;
+mod error;
+mod filter;
+mod lists;
+mod output;
+mod paths;
+mod period;
+mod rollover;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod schema;
+mod storage;
 mod tasks;
+mod time;
 mod ui;
 use cl::*;
+use config::Config;
+use date::DateContext;
+use error::PaskError;
+use filter::Filter;
+use output::{Column, Row, Template};
+use period::Period;
 use tasks::*;
 use ui::run_ui;
 
-/// Gets the path to the current list to manage tasks for.
-fn get_file_name(list_type: Lists) -> String {
-    // Get date information 
-    let date = Local::now().date_naive();
-    let day = date.day().to_string();
-    let month = date.month().to_string();
-    let year = date.year().to_string();
-
-    // Get the date for the first sunday of the current week 
-    let week_day = date.week(Weekday::Sun).first_day().day().to_string();
-
-    let postfix = ".json";
-
-    // Get the name of the list
-    let list_name = match list_type {
-        Lists::Goals => "goals".to_owned(),
-        Lists::Day => day + "-" + &month +  "-" + &year + "-day", 
-        Lists::Week => week_day + "-" + &month + "-" + &year + "-week", 
-        Lists::Month => month + "-month",
-    };
-
-    let file_name = list_name + postfix;
+fn main() {
+    let args: Cli = cl::Cli::parse();
 
-    // Return the full file path to the specific requested list
-    file_name.clone()
+    if let Err(e) = run(args) {
+        eprintln!("pask: {}", e);
+        process::exit(e.exit_code());
+    }
 }
 
-fn main() {
-    let args: Cli = cl::Cli::parse();
+/// Runs the command given on the command line.
+fn run(args: Cli) -> Result<(), PaskError> {
+    let config = Config::load(args.data_dir.as_deref())?;
+
+    // Commands that do not work on a single list
+    if let Commands::Config = args.command {
+        print_config(&config);
+        return Ok(());
+    }
+
+    // Find the data directory, creating it if it does not exist
+    let data_dir = paths::data_dir(config.data_dir.as_deref())?;
+
+    #[cfg(feature = "sqlite")]
+    if let Commands::Migrate = args.command {
+        return migrate(&data_dir);
+    }
 
-    // Change current working directory to home
-    let home = home_dir().unwrap();
-    assert!(env::set_current_dir(home).is_ok());
-    
-    // Create a directory if it does not exist 
-    assert!(fs::create_dir_all(".pask").is_ok());
-
-    assert!(env::set_current_dir(".pask").is_ok());
-    // Get the current file name to load in the struct
-    let file_name = get_file_name(args.list);
-
-    let mut tasks = match Tasks::get_tasks(&file_name) {
-        Ok(x) => x,
-        Err(_) => {
-            println!("The file read failed");
-            return;
+    let storage = storage::open(&data_dir)?;
+
+    // Lists used to be named without years or zero padding
+    let renamed = period::migrate_names(storage.as_ref(), config.week_start, Local::now().date_naive())?;
+    if renamed > 0 {
+        eprintln!("pask: renamed {} lists to the new naming scheme", renamed);
+    }
+
+    if let Commands::List(x) = args.command {
+        return lists::run(x, storage.as_ref());
+    }
+
+    // What relative dates given to commands are worked out from
+    let context = DateContext { today: Local::now().date_naive(), week_start: config.week_start };
+
+    // Get the file name of the list for the chosen date to load in the struct
+    let list = match (args.list, args.list_flag) {
+        (Some(x), Some(y)) if x != y => {
+            return Err(PaskError::Validation(format!(
+                "Two lists were given, {} before the command and {} with --list", x, y
+            )));
         },
+        (x, y) => x.or(y).unwrap_or_else(|| config.default_list.clone()),
+    };
+    let date = match &args.date {
+        Some(x) => date::parse_date(x, context).map_err(PaskError::Validation)?,
+        None => context.today,
+    };
+
+    if let Commands::Agenda = args.command {
+        return agenda(storage.as_ref(), date, context, &config);
+    }
+
+    let period = Period::new(&list, date, config.week_start);
+    let file_name = lists::key(storage.as_ref(), &list, date, config.week_start)?;
+
+    if let Commands::Rollover = args.command {
+        match rollover::rollover(storage.as_ref(), &period, config.sort)? {
+            Some((from, count)) => println!("Carried {} unfinished tasks over from {}", count, from),
+            None => println!("Nothing to carry over"),
+        }
+        return Ok(());
+    }
+
+    let read_only = matches!(args.command, Commands::Display(_));
+    auto_rollover(storage.as_ref(), &list, &period, context, &config, read_only)?;
+
+    // The interface locks, loads and saves each list it opens itself
+    if let Commands::Gui = args.command {
+        return run_ui(storage.as_ref(), list, date, &config);
+    }
+
+    // Hold a lock on the list until the changes are written back, displaying only reads the list
+    // which is always replaced atomically so it does not need one
+    let _lock = match args.command {
+        Commands::Display(_) => None,
+        _ => Some(storage.lock(&file_name)?),
     };
 
+    // Sort first so the indexes given on the command line match the ones displayed
+    let mut tasks = Tasks::get_tasks(storage.as_ref(), &file_name)?;
+    tasks.sort(config.sort);
+
     match args.command {
         Commands::Add(x) => {
             // convert to task struct 
-            let task = Task::from_add(x).unwrap();
+            let task = Task::from_add(x, context)?;
 
             // Add to task file 
-            tasks.add_task(task);
+            tasks.add_task(task, config.sort);
 
-            tasks.write_tasks(&file_name);
+            tasks.write_tasks(storage.as_ref(), &file_name)?;
         },
         Commands::Delete(x) => {
             // Delete the task
-            tasks.del_task(x.desc);
+            tasks.del_task(&x.task)?;
             // Write the task back to the file 
-            tasks.write_tasks(&file_name);
+            tasks.write_tasks(storage.as_ref(), &file_name)?;
         },
 
         Commands::Complete(x) => {
             // Change the task to complete 
-            tasks.complete_task(x.desc);
+            tasks.set_status(&x.task, Status::Done)?;
             // Write the tasks back to the file 
-            tasks.write_tasks(&file_name);
+            tasks.write_tasks(storage.as_ref(), &file_name)?;
+        },
+        Commands::Start(x) => {
+            tasks.set_status(&x.task, Status::InProgress)?;
+            tasks.write_tasks(storage.as_ref(), &file_name)?;
+        },
+        Commands::Block(x) => {
+            tasks.set_status(&x.task, Status::Blocked)?;
+            tasks.write_tasks(storage.as_ref(), &file_name)?;
+        },
+        Commands::Cancel(x) => {
+            tasks.set_status(&x.task, Status::Cancelled)?;
+            tasks.write_tasks(storage.as_ref(), &file_name)?;
         },
-        Commands::Display => {
-            println!("{}", tasks);
+        Commands::Reopen(x) => {
+            tasks.set_status(&x.task, Status::Todo)?;
+            tasks.write_tasks(storage.as_ref(), &file_name)?;
         },
-        Commands::Gui => {
-            match run_ui(tasks) {
-                Ok(mut tasks_new) => tasks_new.write_tasks(&file_name),
-                Err(_) => {
-                    println!("There was an error when running the gui");
-                    return;
-                }
+        Commands::Edit(x) => {
+            // Change the task in place
+            tasks.edit_task(x, context, config.sort)?;
+            // Write the tasks back to the file 
+            tasks.write_tasks(storage.as_ref(), &file_name)?;
+        },
+        Commands::Move(x) => {
+            let to_date = match &x.to_date {
+                Some(text) => date::parse_date(text, context).map_err(PaskError::Validation)?,
+                None => date,
             };
-        }
+            let to = lists::key(storage.as_ref(), &x.to, to_date, config.week_start)?;
+            if to == file_name {
+                return Err(PaskError::Validation(format!("The task is already in {}", to)));
+            }
+
+            tasks.move_task(&x.task, storage.as_ref(), &to, config.sort)?;
+            tasks.write_tasks(storage.as_ref(), &file_name)?;
+        },
+        Commands::Display(x) => {
+            // The flags are extra terms that have to match as well
+            let mut text = x.filter.join(" ");
+            if !text.is_empty() && (!x.tags.is_empty() || x.project.is_some()) {
+                text = format!("({})", text);
+            }
+            for tag in x.tags {
+                text += &format!(" +{}", tag);
+            }
+            if let Some(project) = x.project {
+                text += &format!(" project:{}", project);
+            }
+            let filter = Filter::parse(&text, context).map_err(PaskError::Validation)?;
+            let template = x.template.as_deref().map(Template::parse).transpose().map_err(PaskError::Validation)?;
+
+            let lists = if x.all { every_list(storage.as_ref(), &config)? } else { vec![(period, tasks)] };
+            let keys: Vec<String> = lists.iter().map(|(period, _)| period.key()).collect();
+            let rows: Vec<Row> = lists.iter().zip(&keys)
+                .flat_map(|((_, tasks), key)| tasks.tasks.iter().enumerate().map(move |(i, task)| {
+                    Row { list: key, index: i + 1, task }
+                }))
+                .filter(|x| filter.matches(x.task))
+                .collect();
+
+            match (x.format, template) {
+                (Some(format), _) => {
+                    let columns = if x.fields.is_empty() { Column::ALL.to_vec() } else { x.fields };
+                    print!("{}", output::render(format, &columns, &rows));
+                },
+                (None, Some(template)) => print!("{}", template.render(&rows)),
+                (None, None) if x.all => display_all(&lists, &filter, &config),
+                (None, None) => display(&lists[0].1, &lists[0].0, &filter, &config),
+            }
+        },
+        Commands::Agenda | Commands::Gui | Commands::Rollover | Commands::Config | Commands::List(_) => {
+            unreachable!()
+        },
+        #[cfg(feature = "sqlite")]
+        Commands::Migrate => unreachable!(),
     };
+
+    Ok(())
+}
+
+/// The list for the current period picks up what was left unfinished the first time it is used.
+///
+/// Commands that only read lists do not need locks, so when `read_only` is set a list locked by
+/// another process is left to be carried over the next time instead of failing the command.
+fn auto_rollover(
+    storage: &dyn storage::Storage,
+    list: &ListArg,
+    period: &Period,
+    context: DateContext,
+    config: &Config,
+    read_only: bool,
+) -> Result<(), PaskError> {
+    let is_current = *period == Period::new(list, context.today, config.week_start);
+    if config.auto_rollover && is_current && period.is_dated() {
+        match rollover::rollover(storage, period, config.sort) {
+            Ok(Some((from, count))) => eprintln!("pask: carried {} unfinished tasks over from {}", count, from),
+            Ok(None) => {},
+            Err(PaskError::Locked(_)) if read_only => {},
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Prints the tasks matching `filter`, colored by where each task stands now when writing to a
+/// terminal.
+fn display(tasks: &Tasks, period: &Period, filter: &Filter, config: &Config) {
+    println!("{}", format_list(tasks, period, filter, config));
+}
+
+/// Loads every saved list, sorted the same way as when it is used on its own.
+fn every_list(storage: &dyn storage::Storage, config: &Config) -> Result<Vec<(Period, Tasks)>, PaskError> {
+    let mut lists = vec![];
+    for key in storage.lists()? {
+        if let Some(period) = Period::from_key(&key) {
+            let mut tasks = Tasks::get_tasks(storage, &key)?;
+            tasks.sort(config.sort);
+            lists.push((period, tasks));
+        }
+    }
+    Ok(lists)
+}
+
+/// Prints the tasks matching `filter` in each list, under the title of their list.
+fn display_all(lists: &[(Period, Tasks)], filter: &Filter, config: &Config) {
+    let mut sections = vec![];
+    for (period, tasks) in lists {
+        if !tasks.tasks.iter().any(|x| filter.matches(x)) {
+            continue;
+        }
+
+        let mut section = format!("{}\n", period.title());
+        for line in format_list(tasks, period, filter, config).lines() {
+            section += &format!("  {}\n", line);
+        }
+        sections.push(section);
+    }
+
+    if sections.is_empty() {
+        println!("No tasks match");
+    }
+    print!("{}", sections.join("\n"));
+}
+
+/// Formats a list like `Tasks::format`, coloring each task unless NO_COLOR is set or the output
+/// is piped.
+fn format_list(tasks: &Tasks, period: &Period, filter: &Filter, config: &Config) -> String {
+    let colored = std::env::var_os("NO_COLOR").is_none_or(|x| x.is_empty()) && io::stdout().is_terminal();
+    let now = Local::now().naive_local();
+
+    tasks.format_with(&config.time_format, filter, |task, line| {
+        let color = config.colors.timing(task.timing(now, period.day_for(now)));
+        if !colored || color == tui::style::Color::Reset {
+            return line;
+        }
+        line.with(color.into()).to_string()
+    })
+}
+
+/// Prints the day, week and month lists covering `date` and the goals, each with how many of its
+/// tasks are done. Quarter and year lists are shown too when they have tasks.
+fn agenda(storage: &dyn storage::Storage, date: NaiveDate, context: DateContext, config: &Config) -> Result<(), PaskError> {
+    let mut sections = vec![];
+    for kind in [Lists::Day, Lists::Week, Lists::Month, Lists::Quarter, Lists::Year, Lists::Goals] {
+        let list = ListArg::Kind(kind);
+        let period = Period::new(&list, date, config.week_start);
+        auto_rollover(storage, &list, &period, context, config, true)?;
+
+        let mut tasks = Tasks::get_tasks(storage, &period.key())?;
+        tasks.sort(config.sort);
+        if matches!(kind, Lists::Quarter | Lists::Year) && tasks.tasks.is_empty() {
+            continue;
+        }
+
+        let (done, total) = tasks.progress();
+        let mut section = format!("{} ({}/{} done)\n", period.title(), done, total);
+        if tasks.tasks.is_empty() {
+            section += "  Nothing planned\n";
+        }
+        for line in format_list(&tasks, &period, &Filter::default(), config).lines() {
+            section += &format!("  {}\n", line);
+        }
+        sections.push(section);
+    }
+
+    print!("{}", sections.join("\n"));
+    Ok(())
+}
+
+/// Prints every setting along with where its value came from.
+fn print_config(config: &Config) {
+    match &config.path {
+        Some(path) if path.exists() => println!("# config file: {}", path.display()),
+        Some(path) => println!("# config file: {} (not found)", path.display()),
+        None => println!("# config file: none"),
+    }
+
+    for (key, value) in config.entries() {
+        println!("{} = {:?}  # {}", key, value, config.source(key));
+    }
+}
+
+/// Imports every json list in the data directory into the sqlite database.
+#[cfg(feature = "sqlite")]
+fn migrate(data_dir: &std::path::Path) -> Result<(), PaskError> {
+    let db_path = data_dir.join(sqlite::DB_NAME);
+    let db = sqlite::Sqlite::open(&db_path)?;
+
+    let (count, skipped) = sqlite::import_json(&storage::JsonDir::new(data_dir), &db)?;
+    println!("Imported {} lists into {}", count, db_path.display());
+    if skipped > 0 {
+        println!("Left {} lists that were already in the database as they are", skipped);
+    }
+    Ok(())
 }
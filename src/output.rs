@@ -0,0 +1,269 @@
+// Machine readable output of tasks for scripts, as json, ndjson, csv, tsv or a template
+use clap::ValueEnum;
+use serde_json::Value;
+
+use crate::tasks::Task;
+
+/// Formats tasks can be written in.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, ValueEnum)]
+pub enum Format {
+    /// A json array of objects
+    Json,
+    /// One json object per line
+    Ndjson,
+    /// Comma separated values with a header row
+    Csv,
+    /// Tab separated values with a header row
+    Tsv,
+}
+
+/// Fields of a task that can be written out, their names are part of the output and do not
+/// change.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, ValueEnum)]
+pub enum Column {
+    /// Key of the list the task is in, such as 2026-10-18-day
+    List,
+    /// Index of the task in its list, as shown by display
+    Index,
+    Id,
+    Status,
+    Priority,
+    Desc,
+    /// Start time as HH:MM
+    Start,
+    /// End time as HH:MM
+    End,
+    /// Due date as YYYY-MM-DD, followed by HH:MM when it has a time
+    Due,
+    Tags,
+    Project,
+    Deferrals,
+}
+
+impl Column {
+    /// Every column, in the order they are written when none are picked.
+    pub const ALL: [Column; 12] = [
+        Column::List,
+        Column::Index,
+        Column::Id,
+        Column::Status,
+        Column::Priority,
+        Column::Desc,
+        Column::Start,
+        Column::End,
+        Column::Due,
+        Column::Tags,
+        Column::Project,
+        Column::Deferrals,
+    ];
+
+    /// Gets the name the column is written with.
+    pub fn name(self) -> String {
+        self.to_possible_value().map_or(String::new(), |x| x.get_name().to_owned())
+    }
+
+    /// Gets the value of the column for a task, times and dates are always written the same way
+    /// whatever the configured time format.
+    fn value(self, row: &Row) -> Value {
+        let task = row.task;
+        let text = |x: Option<String>| x.map_or(Value::Null, Value::String);
+        match self {
+            Column::List => row.list.into(),
+            Column::Index => row.index.into(),
+            Column::Id => task.id.clone().into(),
+            Column::Status => task.status.name().into(),
+            Column::Priority => text(task.priority.map(|x| x.name().to_owned())),
+            Column::Desc => task.desc.clone().into(),
+            Column::Start => text(task.start_time.map(|x| x.to_string())),
+            Column::End => text(task.end_time.map(|x| x.to_string())),
+            Column::Due => text(task.due.map(|x| x.to_string())),
+            Column::Tags => task.tags.clone().into(),
+            Column::Project => text(task.project.clone()),
+            Column::Deferrals => task.deferrals.into(),
+        }
+    }
+}
+
+/// A task to write out and where it is.
+pub struct Row<'a> {
+    /// Key of the list the task is in
+    pub list: &'a str,
+    /// Display index of the task, starting from 1
+    pub index: usize,
+    pub task: &'a Task,
+}
+
+/// Writes the rows in a format with the given columns, every line ends with a newline.
+pub fn render(format: Format, columns: &[Column], rows: &[Row]) -> String {
+    let objects = || rows.iter().map(|row| {
+        let fields: Vec<String> = columns.iter()
+            .map(|x| format!("{}:{}", Value::from(x.name()), x.value(row)))
+            .collect();
+        format!("{{{}}}", fields.join(","))
+    });
+
+    match format {
+        Format::Json => format!("[{}]\n", objects().collect::<Vec<_>>().join(",")),
+        Format::Ndjson => objects().map(|x| x + "\n").collect(),
+        Format::Csv | Format::Tsv => {
+            let escape = if format == Format::Csv { csv_escape } else { tsv_escape };
+            let line = |cells: Vec<String>| {
+                let cells: Vec<String> = cells.iter().map(|x| escape(x)).collect();
+                cells.join(if format == Format::Csv { "," } else { "\t" }) + "\n"
+            };
+
+            let mut out = line(columns.iter().map(|x| x.name()).collect());
+            for row in rows {
+                out += &line(columns.iter().map(|x| plain(&x.value(row))).collect());
+            }
+            out
+        },
+    }
+}
+
+/// A line of text with `{field}` placeholders, such as `{id} {desc}`. `{{` and `}}` are written
+/// as braces.
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+enum Part {
+    Text(String),
+    Column(Column),
+}
+
+impl Template {
+    /// Parses a template, failing on fields that do not exist.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parts = vec![];
+        let mut literal = String::new();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                },
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                },
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(x) => name.push(x),
+                            None => return Err(format!("{{{} in the template has no }} after it", name)),
+                        }
+                    }
+                    let column = Column::from_str(&name, false).map_err(|_| format!(
+                        "{{{}}} is not a field, use {}",
+                        name,
+                        Column::ALL.map(|x| format!("{{{}}}", x.name())).join(", ")
+                    ))?;
+                    parts.push(Part::Text(std::mem::take(&mut literal)));
+                    parts.push(Part::Column(column));
+                },
+                '}' => return Err("A } in the template has no { before it, write }} for a brace".to_owned()),
+                _ => literal.push(c),
+            }
+        }
+        parts.push(Part::Text(literal));
+        Ok(Self { parts })
+    }
+
+    /// Writes a line for each row.
+    pub fn render(&self, rows: &[Row]) -> String {
+        let mut out = String::new();
+        for row in rows {
+            for part in &self.parts {
+                match part {
+                    Part::Text(text) => out += text,
+                    Part::Column(column) => out += &plain(&column.value(row)),
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Gets a value as plain text, missing values are empty and tags are separated by spaces.
+fn plain(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items.iter().map(plain).collect::<Vec<_>>().join(" "),
+        other => other.to_string(),
+    }
+}
+
+/// Quotes a csv cell when it has a comma, quote or line break in it.
+fn csv_escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_owned()
+    }
+}
+
+/// Writes tabs, line breaks and backslashes in a tsv cell as `\t`, `\n`, `\r` and `\\`.
+fn tsv_escape(cell: &str) -> String {
+    cell.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tasks::{Priority, Status};
+
+    fn task() -> Task {
+        let mut task = Task::new();
+        task.desc = "Say \"hi\", then\tleave".to_owned();
+        task.id = "0c1f5e6a2b8d4f3e9a7c6b5d4e3f2a1b".to_owned();
+        task.status = Status::InProgress;
+        task.priority = Some(Priority::High);
+        task.start_time = "09:30".parse().ok();
+        task.tags = vec!["work".to_owned(), "home".to_owned()];
+        task
+    }
+
+    #[test]
+    fn formats_keep_field_order_and_escape() {
+        let task = task();
+        let rows = [Row { list: "2026-10-18-day", index: 2, task: &task }];
+        let columns = [Column::Index, Column::Desc, Column::Start, Column::Due, Column::Tags];
+
+        assert_eq!(
+            render(Format::Ndjson, &columns, &rows),
+            "{\"index\":2,\"desc\":\"Say \\\"hi\\\", then\\tleave\",\"start\":\"09:30\",\"due\":null,\"tags\":[\"work\",\"home\"]}\n",
+        );
+        assert_eq!(render(Format::Json, &columns, &[]), "[]\n");
+        assert_eq!(
+            render(Format::Csv, &columns, &rows),
+            "index,desc,start,due,tags\n2,\"Say \"\"hi\"\", then\tleave\",09:30,,work home\n",
+        );
+        assert_eq!(
+            render(Format::Tsv, &columns, &rows),
+            "index\tdesc\tstart\tdue\ttags\n2\tSay \"hi\", then\\tleave\t09:30\t\twork home\n",
+        );
+
+        let value: Value = serde_json::from_str(&render(Format::Json, &Column::ALL, &rows)).unwrap();
+        assert_eq!(value[0]["status"], "in-progress");
+        assert_eq!(value[0]["priority"], "high");
+        assert_eq!(value[0]["project"], Value::Null);
+    }
+
+    #[test]
+    fn templates_fill_in_fields() {
+        let task = task();
+        let rows = [Row { list: "goals", index: 1, task: &task }];
+        let template = Template::parse("{{{list}}} {id}: {priority} {project}").unwrap();
+        assert_eq!(template.render(&rows), "{goals} 0c1f5e6a2b8d4f3e9a7c6b5d4e3f2a1b: high \n");
+
+        assert!(Template::parse("{colour}").is_err());
+        assert!(Template::parse("{id").is_err());
+        assert!(Template::parse("id}").is_err());
+    }
+}
@@ -0,0 +1,77 @@
+// Works out where pask keeps its data
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::PaskError;
+
+/// Environment variable that overrides the data directory.
+pub const HOME_VAR: &str = "PASK_HOME";
+
+/// Gets the directory lists are stored in and makes sure it exists.
+///
+/// This is the configured directory (from the `--data-dir` flag, `$PASK_HOME` or the config file)
+/// or else `pask` inside the platform data directory (`$XDG_DATA_HOME` on Linux). Lists in the old
+/// `~/.pask` directory are moved to the platform directory the first time it is used.
+pub fn data_dir(configured: Option<&Path>) -> Result<PathBuf, PaskError> {
+    let dir = match configured {
+        Some(x) => x.to_path_buf(),
+        None => {
+            let dir = default_data_dir()?;
+            migrate_legacy(&dir)?;
+            dir
+        },
+    };
+
+    fs::create_dir_all(&dir).map_err(|e| PaskError::io(&dir, e))?;
+    Ok(dir)
+}
+
+/// Gets `pask` inside the platform data directory.
+pub fn default_data_dir() -> Result<PathBuf, PaskError> {
+    dirs::data_dir().map(|x| x.join("pask")).ok_or_else(|| {
+        PaskError::Validation(format!("could not find a data directory, set {} instead", HOME_VAR))
+    })
+}
+
+/// Moves lists out of `~/.pask`, where pask used to keep them, if `dir` has not been made yet.
+fn migrate_legacy(dir: &Path) -> Result<(), PaskError> {
+    let legacy = match dirs::home_dir() {
+        Some(home) => home.join(".pask"),
+        None => return Ok(()),
+    };
+    if dir.exists() || !legacy.is_dir() {
+        return Ok(());
+    }
+
+    if let Some(parent) = dir.parent() {
+        fs::create_dir_all(parent).map_err(|e| PaskError::io(parent, e))?;
+    }
+
+    // Renaming fails across file systems, the files are copied instead and the old directory is
+    // left in place
+    if fs::rename(&legacy, dir).is_err() {
+        fs::create_dir_all(dir).map_err(|e| PaskError::io(dir, e))?;
+        for entry in fs::read_dir(&legacy).map_err(|e| PaskError::io(&legacy, e))? {
+            let path = entry.map_err(|e| PaskError::io(&legacy, e))?.path();
+            if !path.is_file() {
+                continue;
+            }
+            if let Some(name) = path.file_name() {
+                fs::copy(&path, dir.join(name)).map_err(|e| PaskError::io(&path, e))?;
+            }
+        }
+    }
+
+    eprintln!("pask: moved your lists from {} to {}", legacy.display(), dir.display());
+    Ok(())
+}
+
+/// Expands a leading `~` to the home directory.
+pub fn expand_home(text: &str) -> PathBuf {
+    match (text.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        },
+        _ => PathBuf::from(text),
+    }
+}
@@ -0,0 +1,280 @@
+// The span of time a list covers and the key it is stored under
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Weekday};
+
+use crate::cl::{ListArg, Lists};
+use crate::error::PaskError;
+use crate::storage::Storage;
+
+/// The span of time a list covers.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Period {
+    /// Goals are not tied to a date
+    Goals,
+    /// Neither are lists made with `pask list create`
+    Named(String),
+    Day(NaiveDate),
+    /// A week, given by the day it starts on
+    Week(NaiveDate),
+    Month { year: i32, month: u32 },
+    /// Quarters are numbered from 1 to 4
+    Quarter { year: i32, quarter: u32 },
+    Year(i32),
+}
+
+impl Period {
+    /// Gets the period of the given list that covers a date.
+    pub fn new(list: &ListArg, date: NaiveDate, week_start: Weekday) -> Self {
+        match list {
+            ListArg::Kind(Lists::Goals) => Period::Goals,
+            ListArg::Kind(Lists::Day) => Period::Day(date),
+            ListArg::Kind(Lists::Week) => Period::Week(date.week(week_start).first_day()),
+            ListArg::Kind(Lists::Month) => Period::Month { year: date.year(), month: date.month() },
+            ListArg::Kind(Lists::Quarter) => Period::Quarter { year: date.year(), quarter: date.month0() / 3 + 1 },
+            ListArg::Kind(Lists::Year) => Period::Year(date.year()),
+            ListArg::Named(name) => Period::Named(name.clone()),
+        }
+    }
+
+    /// Whether the list covers a span of time, rather than being goals or a named list.
+    pub fn is_dated(&self) -> bool {
+        !matches!(self, Period::Goals | Period::Named(_))
+    }
+
+    /// Gets the day the times of tasks in the list are on, only day lists are tied to a single day.
+    pub fn day_for(&self, now: NaiveDateTime) -> NaiveDate {
+        match self {
+            Period::Day(date) => *date,
+            _ => now.date(),
+        }
+    }
+
+    /// Gets a heading for the list, such as `Sunday 2026-10-18` or `Week of 2026-10-18`.
+    pub fn title(&self) -> String {
+        match self {
+            Period::Goals => "Goals".to_owned(),
+            Period::Named(name) => name.clone(),
+            Period::Day(date) => date.format("%A %Y-%m-%d").to_string(),
+            Period::Week(date) => format!("Week of {}", date.format("%Y-%m-%d")),
+            Period::Month { year, month } => {
+                NaiveDate::from_ymd_opt(*year, *month, 1).map_or_else(|| self.key(), |x| x.format("%B %Y").to_string())
+            },
+            Period::Quarter { year, quarter } => format!("Q{} {}", quarter, year),
+            Period::Year(year) => year.to_string(),
+        }
+    }
+
+    /// Gets the key the list is stored under, `goals`, `work-list`, `2026-10-18-day`,
+    /// `2026-10-18-week` (the first day of the week), `2026-10-month`, `2026-Q4-quarter` or
+    /// `2026-year`.
+    ///
+    /// Keys sort in date order within each kind of list, and end with the kind after the last `-`.
+    pub fn key(&self) -> String {
+        match self {
+            Period::Goals => "goals".to_owned(),
+            Period::Named(name) => format!("{}-list", name),
+            Period::Day(date) => format!("{}-day", date.format("%Y-%m-%d")),
+            Period::Week(date) => format!("{}-week", date.format("%Y-%m-%d")),
+            Period::Month { year, month } => format!("{:04}-{:02}-month", year, month),
+            Period::Quarter { year, quarter } => format!("{:04}-Q{}-quarter", year, quarter),
+            Period::Year(year) => format!("{:04}-year", year),
+        }
+    }
+
+    /// Inverse of `key`.
+    pub fn from_key(key: &str) -> Option<Self> {
+        if key == "goals" {
+            return Some(Period::Goals);
+        }
+
+        let (date, kind) = key.rsplit_once('-')?;
+        match kind {
+            // Names of built in lists are never used for named lists
+            "list" => match date.parse() {
+                Ok(ListArg::Named(name)) => Some(Period::Named(name)),
+                _ => None,
+            },
+            "day" => NaiveDate::parse_from_str(date, "%Y-%m-%d").ok().map(Period::Day),
+            "week" => NaiveDate::parse_from_str(date, "%Y-%m-%d").ok().map(Period::Week),
+            "month" => {
+                let (year, month) = date.split_once('-')?;
+                let (year, month) = (year.parse().ok()?, month.parse().ok()?);
+                NaiveDate::from_ymd_opt(year, month, 1)?;
+                Some(Period::Month { year, month })
+            },
+            "quarter" => {
+                let (year, quarter) = date.split_once("-Q")?;
+                let (year, quarter) = (year.parse().ok()?, quarter.parse().ok()?);
+                (1..=4).contains(&quarter).then_some(Period::Quarter { year, quarter })
+            },
+            "year" => date.parse().ok().map(Period::Year),
+            _ => None,
+        }
+    }
+
+    /// Works out the period of a list saved under the names pask used before keys had years and
+    /// zero padding: `D-M-Y-day`, `D-M-Y-week` and `M-month`.
+    ///
+    /// Weeks were named by the Sunday they start on, whatever `week_start` is now, but the month
+    /// and year of the day the list was made. A week starting in the previous month is recognised
+    /// by which of the two dates is a Sunday, and the week is then moved onto the `week_start`
+    /// week sharing most of its days. Months had no year, they are taken to be the latest such
+    /// month up to `today`.
+    fn from_legacy(name: &str, week_start: Weekday, today: NaiveDate) -> Option<Self> {
+        let (rest, kind) = name.rsplit_once('-')?;
+        let numbers = rest.split('-')
+            .map(|x| if x.is_empty() || x.len() > 4 { None } else { x.parse::<u32>().ok() })
+            .collect::<Option<Vec<u32>>>()?;
+
+        match (kind, numbers.as_slice()) {
+            ("day", [day, month, year]) if *day < 100 && *month < 100 => {
+                NaiveDate::from_ymd_opt(i32::try_from(*year).ok()?, *month, *day).map(Period::Day)
+            },
+            ("week", [day, month, year]) if *day < 100 && *month < 100 => {
+                let year = i32::try_from(*year).ok()?;
+                let named = NaiveDate::from_ymd_opt(year, *month, *day);
+                let previous_month = match month {
+                    1 => NaiveDate::from_ymd_opt(year - 1, 12, *day),
+                    _ => NaiveDate::from_ymd_opt(year, month - 1, *day),
+                };
+
+                let starts_week = |x: &Option<NaiveDate>| x.is_some_and(|x| x.weekday() == Weekday::Sun);
+                let sunday = if !starts_week(&named) && starts_week(&previous_month) { previous_month } else { named }?;
+
+                // The middle of the old week falls in the week sharing most of its days
+                let middle = sunday + chrono::Duration::days(3);
+                Some(Period::Week(middle.week(week_start).first_day()))
+            },
+            ("month", [month]) if (1..=12).contains(month) => {
+                let year = if *month <= today.month() { today.year() } else { today.year() - 1 };
+                Some(Period::Month { year, month: *month })
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Renames lists saved under the old names to their period keys.
+///
+/// When a list already exists under the new key the tasks of both are kept, tasks with the same
+/// id are only kept once. Returns how many lists were renamed.
+pub fn migrate_names(storage: &dyn Storage, week_start: Weekday, today: NaiveDate) -> Result<usize, PaskError> {
+    let mut count = 0;
+    for name in storage.lists()? {
+        let key = match Period::from_legacy(&name, week_start, today) {
+            Some(period) => period.key(),
+            None => continue,
+        };
+
+        let _old_lock = storage.lock(&name)?;
+        let _new_lock = storage.lock(&key)?;
+
+        let mut tasks = storage.load(&key)?;
+        for task in storage.load(&name)?.tasks {
+            if !tasks.tasks.iter().any(|x| x.id == task.id) {
+                tasks.tasks.push(task);
+            }
+        }
+        tasks.tasks.sort();
+
+        // Only remove the old list once its tasks are safely under the new key
+        storage.save(&key, &tasks)?;
+        storage.delete(&name)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Memory;
+    use crate::tasks::{Task, Tasks};
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn keys_round_trip() {
+        let periods = [
+            Period::Goals,
+            Period::Named("sprint-42".to_owned()),
+            Period::Day(date(2026, 1, 5)),
+            Period::Week(date(2025, 12, 28)),
+            Period::Month { year: 2026, month: 3 },
+            Period::Quarter { year: 2026, quarter: 4 },
+            Period::Year(2027),
+        ];
+        for period in periods {
+            assert_eq!(Period::from_key(&period.key()), Some(period));
+        }
+        assert_eq!(Period::from_key("day-list"), None);
+        assert_eq!(Period::from_key("2026-Q5-quarter"), None);
+
+        let quarter = |month| Period::new(&ListArg::Kind(Lists::Quarter), date(2026, month, 15), Weekday::Sun);
+        assert_eq!(quarter(3), Period::Quarter { year: 2026, quarter: 1 });
+        assert_eq!(quarter(4), Period::Quarter { year: 2026, quarter: 2 });
+        assert_eq!(quarter(12), Period::Quarter { year: 2026, quarter: 4 });
+        assert_eq!(Period::Month { year: 2026, month: 3 }.key(), "2026-03-month");
+    }
+
+    #[test]
+    fn legacy_names_map_to_periods() {
+        let today = date(2026, 10, 18);
+        let legacy = |name| Period::from_legacy(name, Weekday::Sun, today);
+
+        assert_eq!(legacy("5-1-2026-day"), Some(Period::Day(date(2026, 1, 5))));
+        assert_eq!(legacy("18-10-2026-week"), Some(Period::Week(date(2026, 10, 18))));
+        // Made on Thursday 1 October for the week starting Sunday 27 September
+        assert_eq!(legacy("27-10-2026-week"), Some(Period::Week(date(2026, 9, 27))));
+        // Made on Friday 1 January for the week starting Sunday 27 December
+        assert_eq!(legacy("27-1-2021-week"), Some(Period::Week(date(2020, 12, 27))));
+        // Old weeks always started on Sunday, they become the Monday week they mostly overlap
+        let monday = |name| Period::from_legacy(name, Weekday::Mon, today);
+        assert_eq!(monday("18-10-2026-week"), Some(Period::Week(date(2026, 10, 19))));
+        assert_eq!(monday("27-10-2026-week"), Some(Period::Week(date(2026, 9, 28))));
+        assert_eq!(
+            Period::from_legacy("18-10-2026-week", Weekday::Sat, today),
+            Some(Period::Week(date(2026, 10, 17))),
+        );
+        assert_eq!(legacy("10-month"), Some(Period::Month { year: 2026, month: 10 }));
+        assert_eq!(legacy("11-month"), Some(Period::Month { year: 2025, month: 11 }));
+
+        // Current keys and other names are left alone
+        assert_eq!(legacy("2026-10-18-day"), None);
+        assert_eq!(legacy("2026-10-month"), None);
+        assert_eq!(legacy("goals"), None);
+        assert_eq!(legacy("31-2-2026-day"), None);
+    }
+
+    #[test]
+    fn only_day_lists_have_their_own_day() {
+        let now = date(2026, 10, 18).and_hms_opt(9, 30, 0).unwrap();
+        assert_eq!(Period::Day(date(2026, 10, 20)).day_for(now), date(2026, 10, 20));
+        assert_eq!(Period::Week(date(2026, 10, 25)).day_for(now), date(2026, 10, 18));
+        assert_eq!(Period::Goals.day_for(now), date(2026, 10, 18));
+    }
+
+    #[test]
+    fn legacy_lists_merge_into_existing_keys() {
+        let storage = Memory::default();
+        let task = |desc: &str, id: &str| Task { desc: desc.to_owned(), id: id.to_owned(), ..Task::new() };
+        let tasks = |tasks: Vec<Task>| Tasks { tasks };
+        storage.save("18-10-2026-day", &tasks(vec![task("Old", "a"), task("Both", "b")])).unwrap();
+        storage.save("2026-10-18-day", &tasks(vec![task("Both, edited", "b"), task("New", "c")])).unwrap();
+        storage.save("goals", &tasks(vec![task("Goal", "d")])).unwrap();
+
+        assert_eq!(migrate_names(&storage, Weekday::Sun, date(2026, 10, 18)).unwrap(), 1);
+        assert_eq!(storage.lists().unwrap(), ["2026-10-18-day", "goals"]);
+
+        let mut merged: Vec<(String, String)> = storage.load("2026-10-18-day").unwrap().tasks.into_iter()
+            .map(|x| (x.id, x.desc))
+            .collect();
+        merged.sort();
+        let expected = [("a", "Old"), ("b", "Both, edited"), ("c", "New")].map(|(id, desc)| (id.to_owned(), desc.to_owned()));
+        assert_eq!(merged, expected);
+
+        // Nothing is left to rename the second time
+        assert_eq!(migrate_names(&storage, Weekday::Sun, date(2026, 10, 18)).unwrap(), 0);
+    }
+}
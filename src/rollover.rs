@@ -0,0 +1,151 @@
+// Carries unfinished tasks from earlier lists into later ones
+use std::mem;
+
+use crate::error::PaskError;
+use crate::period::Period;
+use crate::storage::Storage;
+use crate::tasks::{SortKey, Status};
+
+/// Carries the unfinished tasks of the latest earlier list of the same kind into the list for
+/// `period`.
+///
+/// The originals are marked as carried over so they are only ever carried once, and the copies
+/// keep their ids with one more deferral counted. Returns the name of the list the tasks came
+/// from and how many were carried, or none when there was nothing to carry.
+pub fn rollover(storage: &dyn Storage, period: &Period, sort: SortKey) -> Result<Option<(String, usize)>, PaskError> {
+    if !period.is_dated() {
+        return Err(PaskError::Validation(
+            "goals and named lists are not tied to a date and never roll over".to_owned()
+        ));
+    }
+
+    let key = period.key();
+    let previous = match previous_list(storage, period)? {
+        Some(x) => x,
+        None => return Ok(None),
+    };
+
+    // Most of the time there is nothing to carry, so do not lock lists another process may have
+    // open unless there is
+    if !storage.load(&previous)?.tasks.iter().any(|x| x.is_unfinished()) {
+        return Ok(None);
+    }
+
+    let _previous_lock = storage.lock(&previous)?;
+    let _lock = storage.lock(&key)?;
+
+    let mut from = storage.load(&previous)?;
+    let mut to = storage.load(&key)?;
+    let mut count = 0;
+    for task in from.tasks.iter_mut().filter(|x| x.is_unfinished()) {
+        let mut copy = task.clone();
+        copy.deferrals += 1;
+        if !to.tasks.iter().any(|x| x.id == copy.id) {
+            to.add_task(copy, sort);
+        }
+        task.status = Status::CarriedOver;
+        count += 1;
+    }
+    if count == 0 {
+        return Ok(None);
+    }
+
+    // Save the copies first so a failure never leaves tasks marked as carried with nowhere to go
+    storage.save(&key, &to)?;
+    storage.save(&previous, &from)?;
+    Ok(Some((previous, count)))
+}
+
+/// Gets the name of the latest saved list of the same kind as `period` that comes before it.
+fn previous_list(storage: &dyn Storage, period: &Period) -> Result<Option<String>, PaskError> {
+    let key = period.key();
+    let same_kind = |x: &str| {
+        Period::from_key(x).is_some_and(|x| mem::discriminant(&x) == mem::discriminant(period))
+    };
+
+    // Keys of the same kind sort in date order
+    Ok(storage.lists()?.into_iter().filter(|x| same_kind(x) && *x < key).max())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::storage::Memory;
+    use crate::tasks::{Task, Tasks};
+
+    fn task(desc: &str, id: &str, status: Status) -> Task {
+        Task { desc: desc.to_owned(), id: id.to_owned(), status, ..Task::new() }
+    }
+
+    fn today() -> Period {
+        Period::Day(NaiveDate::from_ymd_opt(2026, 10, 18).unwrap())
+    }
+
+    /// A store with yesterday's list holding a task of each kind.
+    fn storage() -> Memory {
+        let storage = Memory::default();
+        let yesterday = Tasks {
+            tasks: vec![
+                task("Report", "a", Status::Todo),
+                task("Standup", "b", Status::Done),
+                task("Review", "c", Status::InProgress),
+            ],
+        };
+        storage.save("2026-10-17-day", &yesterday).unwrap();
+        storage.save("2026-10-18-week", &Tasks { tasks: vec![task("Other kind", "d", Status::Todo)] }).unwrap();
+        storage
+    }
+
+    fn summary(tasks: &Tasks) -> Vec<(&str, Status, u32)> {
+        tasks.tasks.iter().map(|x| (x.id.as_str(), x.status, x.deferrals)).collect()
+    }
+
+    #[test]
+    fn unfinished_tasks_are_carried_and_marked() {
+        let storage = storage();
+        let carried = rollover(&storage, &today(), SortKey::Time).unwrap();
+        assert_eq!(carried, Some(("2026-10-17-day".to_owned(), 2)));
+
+        assert_eq!(
+            summary(&storage.load("2026-10-18-day").unwrap()),
+            [("a", Status::Todo, 1), ("c", Status::InProgress, 1)],
+        );
+        assert_eq!(
+            summary(&storage.load("2026-10-17-day").unwrap()),
+            [("a", Status::CarriedOver, 0), ("b", Status::Done, 0), ("c", Status::CarriedOver, 0)],
+        );
+
+        // The next day carries the copies on, counting another deferral
+        let tomorrow = Period::Day(NaiveDate::from_ymd_opt(2026, 10, 19).unwrap());
+        assert_eq!(rollover(&storage, &tomorrow, SortKey::Time).unwrap(), Some(("2026-10-18-day".to_owned(), 2)));
+        assert_eq!(
+            summary(&storage.load("2026-10-19-day").unwrap()),
+            [("a", Status::Todo, 2), ("c", Status::InProgress, 2)],
+        );
+    }
+
+    #[test]
+    fn tasks_are_not_carried_twice() {
+        let storage = storage();
+        rollover(&storage, &today(), SortKey::Time).unwrap();
+        assert_eq!(rollover(&storage, &today(), SortKey::Time).unwrap(), None);
+
+        // Reopening the original does not add a second copy of it
+        let mut yesterday = storage.load("2026-10-17-day").unwrap();
+        yesterday.tasks[0].status = Status::Todo;
+        storage.save("2026-10-17-day", &yesterday).unwrap();
+        assert_eq!(rollover(&storage, &today(), SortKey::Time).unwrap(), Some(("2026-10-17-day".to_owned(), 1)));
+        assert_eq!(
+            summary(&storage.load("2026-10-18-day").unwrap()),
+            [("a", Status::Todo, 1), ("c", Status::InProgress, 1)],
+        );
+    }
+
+    #[test]
+    fn undated_lists_do_not_roll_over() {
+        assert!(rollover(&storage(), &Period::Goals, SortKey::Time).is_err());
+        assert_eq!(rollover(&Memory::default(), &today(), SortKey::Time).unwrap(), None);
+    }
+}
@@ -0,0 +1,220 @@
+// Versioning of the json format lists are saved in, and upgrades from older versions
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::tasks::{new_id, Tasks};
+use crate::time::TimeOfDay;
+
+/// Version of the format written by this build of pask.
+pub const CURRENT_VERSION: u64 = 8;
+
+/// A list as it is written to disk, tagged with the format version.
+#[derive(Serialize)]
+pub struct Versioned<'a> {
+    pub version: u64,
+    #[serde(flatten)]
+    pub tasks: &'a Tasks,
+}
+
+impl<'a> Versioned<'a> {
+    pub fn new(tasks: &'a Tasks) -> Self {
+        Self { version: CURRENT_VERSION, tasks }
+    }
+}
+
+/// Upgrades to get from each version to the next, `UPGRADES[n]` takes version `n` to `n + 1`.
+const UPGRADES: [fn(&mut Value); CURRENT_VERSION as usize] = [
+    v0_to_v1,
+    v1_to_v2,
+    v2_to_v3,
+    v3_to_v4,
+    v4_to_v5,
+    v5_to_v6,
+    v6_to_v7,
+    v7_to_v8,
+];
+
+/// Gets the format version of a parsed list, files written before versioning are version 0.
+pub fn version_of(value: &Value) -> u64 {
+    value.get("version").and_then(Value::as_u64).unwrap_or(0)
+}
+
+/// Brings a parsed list up to the current version.
+///
+/// Returns the version the list was in before the upgrade, or fails with it if it is newer than
+/// this build understands.
+pub fn upgrade(value: &mut Value) -> Result<u64, u64> {
+    let version = version_of(value);
+    if version > CURRENT_VERSION {
+        return Err(version);
+    }
+
+    for upgrade in &UPGRADES[version as usize..] {
+        upgrade(value);
+    }
+    if let Some(object) = value.as_object_mut() {
+        object.insert("version".to_owned(), CURRENT_VERSION.into());
+    }
+
+    Ok(version)
+}
+
+/// Version 1 only adds the version field.
+fn v0_to_v1(_value: &mut Value) {}
+
+/// Version 2 gives every task a unique id.
+fn v1_to_v2(value: &mut Value) {
+    for task in tasks_mut(value) {
+        task.entry("id").or_insert_with(|| new_id().into());
+    }
+}
+
+/// Version 3 replaces the `completed` flag with a status.
+fn v2_to_v3(value: &mut Value) {
+    for task in tasks_mut(value) {
+        let completed = task.remove("completed").and_then(|x| x.as_bool()).unwrap_or(false);
+        task.insert("status".to_owned(), if completed { "done" } else { "todo" }.into());
+    }
+}
+
+/// Version 4 writes times as `HH:MM` text instead of `[hour, minute]`. Times that were never
+/// valid, such as `[99, 99]` or `[256, 30]`, are dropped.
+fn v3_to_v4(value: &mut Value) {
+    for task in tasks_mut(value) {
+        for key in ["start_time", "end_time"] {
+            let time = task.get(key)
+                .and_then(Value::as_array)
+                .and_then(|x| match x.as_slice() {
+                    [hour, minute] => TimeOfDay::new(
+                        u8::try_from(hour.as_u64()?).ok()?,
+                        u8::try_from(minute.as_u64()?).ok()?,
+                    ),
+                    _ => None,
+                });
+            task.insert(key.to_owned(), time.map_or(Value::Null, |x| x.to_string().into()));
+        }
+    }
+}
+
+/// Version 5 adds an optional due date.
+fn v4_to_v5(value: &mut Value) {
+    for task in tasks_mut(value) {
+        task.entry("due").or_insert(Value::Null);
+    }
+}
+
+/// Version 6 counts how many times each task has been carried over.
+fn v5_to_v6(value: &mut Value) {
+    for task in tasks_mut(value) {
+        task.entry("deferrals").or_insert(0.into());
+    }
+}
+
+/// Version 7 adds an optional priority.
+fn v6_to_v7(value: &mut Value) {
+    for task in tasks_mut(value) {
+        task.entry("priority").or_insert(Value::Null);
+    }
+}
+
+/// Version 8 adds tags and an optional project.
+fn v7_to_v8(value: &mut Value) {
+    for task in tasks_mut(value) {
+        task.entry("tags").or_insert_with(|| Value::Array(vec![]));
+        task.entry("project").or_insert(Value::Null);
+    }
+}
+
+/// Gets every task object in a parsed list.
+fn tasks_mut(value: &mut Value) -> impl Iterator<Item = &mut serde_json::Map<String, Value>> {
+    value.get_mut("tasks")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object_mut)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every format that has ever been written, oldest first.
+    const HISTORICAL: [(u64, &str); 9] = [
+        (0, include_str!("../tests/golden/v0.json")),
+        (1, include_str!("../tests/golden/v1.json")),
+        (2, include_str!("../tests/golden/v2.json")),
+        (3, include_str!("../tests/golden/v3.json")),
+        (4, include_str!("../tests/golden/v4.json")),
+        (5, include_str!("../tests/golden/v5.json")),
+        (6, include_str!("../tests/golden/v6.json")),
+        (7, include_str!("../tests/golden/v7.json")),
+        (8, include_str!("../tests/golden/v8.json")),
+    ];
+
+    const CURRENT: &str = include_str!("../tests/golden/current.json");
+
+    #[test]
+    fn every_version_is_covered() {
+        assert_eq!(HISTORICAL.len() as u64, CURRENT_VERSION + 1);
+    }
+
+    #[test]
+    fn historical_formats_upgrade_to_current() {
+        for (version, golden) in HISTORICAL {
+            let mut value: Value = serde_json::from_str(golden).unwrap();
+            assert_eq!(upgrade(&mut value), Ok(version));
+
+            // Later formats were written with more tasks, older files match the first ones
+            let mut expected: Value = serde_json::from_str(CURRENT).unwrap();
+            let count = value["tasks"].as_array().unwrap().len();
+            expected["tasks"].as_array_mut().unwrap().truncate(count);
+
+            copy_generated_ids(&mut value, &expected);
+            assert_eq!(value, expected, "upgrading version {}", version);
+        }
+    }
+
+    /// Ids made during an upgrade are random, once they are checked to be unique they are replaced
+    /// with the ones in the golden file so the rest of the list can be compared.
+    fn copy_generated_ids(value: &mut Value, expected: &Value) {
+        let expected_ids: Vec<&Value> = expected["tasks"].as_array().unwrap().iter()
+            .map(|task| &task["id"])
+            .collect();
+
+        let mut seen = std::collections::HashSet::new();
+        for (task, expected_id) in tasks_mut(value).zip(expected_ids) {
+            let id = task["id"].as_str().unwrap().to_owned();
+            assert!(!id.is_empty() && seen.insert(id), "ids should be unique");
+            task.insert("id".to_owned(), expected_id.clone());
+        }
+    }
+
+    #[test]
+    fn invalid_times_are_dropped() {
+        let cases = [
+            (0, r#"{"tasks":[{"desc":"x","start_time":[99,99],"end_time":[9,45],"completed":false}]}"#),
+            (3, r#"{"version":3,"tasks":[{"desc":"x","start_time":[99,99],"end_time":[9,45],"status":"todo","id":"a"}]}"#),
+            (3, r#"{"version":3,"tasks":[{"desc":"x","start_time":[256,30],"end_time":[9,45],"status":"todo","id":"a"}]}"#),
+            (3, r#"{"version":3,"tasks":[{"desc":"x","start_time":[9,286],"end_time":[9,45],"status":"todo","id":"a"}]}"#),
+        ];
+        for (version, text) in cases {
+            let mut value: Value = serde_json::from_str(text).unwrap();
+            assert_eq!(upgrade(&mut value), Ok(version));
+            assert_eq!(value["tasks"][0]["start_time"], Value::Null, "{}", text);
+            assert_eq!(value["tasks"][0]["end_time"], "09:45", "{}", text);
+        }
+    }
+
+    #[test]
+    fn current_format_round_trips() {
+        let tasks: Tasks = serde_json::from_str(CURRENT).unwrap();
+        let written = serde_json::to_value(Versioned::new(&tasks)).unwrap();
+        assert_eq!(written, serde_json::from_str::<Value>(CURRENT).unwrap());
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        let mut value: Value = serde_json::from_str(r#"{"version":999,"tasks":[]}"#).unwrap();
+        assert_eq!(upgrade(&mut value), Err(999));
+    }
+}
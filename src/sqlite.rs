@@ -0,0 +1,258 @@
+// Storage backend that keeps every list in a single sqlite database
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection};
+
+use crate::error::PaskError;
+use crate::storage::{ListLock, Storage};
+use crate::tasks::{Priority, Status, Task, Tasks};
+use crate::time::TimeOfDay;
+
+/// File name of the database inside the data directory.
+pub const DB_NAME: &str = "pask.db";
+
+/// Statements to get the database from each schema version to the next, the version the
+/// database is in is kept in `PRAGMA user_version`. Databases created before versioning are at
+/// version 0 but already have the tables, so the first step must not fail on them.
+const MIGRATIONS: [&str; 8] = ["
+    CREATE TABLE IF NOT EXISTS tasks (
+        id INTEGER PRIMARY KEY,
+        list_kind TEXT NOT NULL,
+        period TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        desc TEXT NOT NULL,
+        start_time TEXT,
+        end_time TEXT,
+        completed INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS tasks_list ON tasks (list_kind, period);
+", "
+    ALTER TABLE tasks ADD COLUMN task_id TEXT NOT NULL DEFAULT '';
+    UPDATE tasks SET task_id = lower(hex(randomblob(16)));
+    CREATE INDEX tasks_task_id ON tasks (task_id);
+", "
+    ALTER TABLE tasks ADD COLUMN status TEXT NOT NULL DEFAULT 'todo';
+    UPDATE tasks SET status = 'done' WHERE completed;
+    ALTER TABLE tasks DROP COLUMN completed;
+", "
+    ALTER TABLE tasks ADD COLUMN due TEXT;
+", "
+    ALTER TABLE tasks ADD COLUMN deferrals INTEGER NOT NULL DEFAULT 0;
+", "
+    CREATE TABLE lists (
+        list_kind TEXT NOT NULL,
+        period TEXT NOT NULL,
+        PRIMARY KEY (list_kind, period)
+    );
+    INSERT INTO lists SELECT DISTINCT list_kind, period FROM tasks;
+", "
+    ALTER TABLE tasks ADD COLUMN priority TEXT;
+", "
+    ALTER TABLE tasks ADD COLUMN tags TEXT NOT NULL DEFAULT '';
+    ALTER TABLE tasks ADD COLUMN project TEXT;
+"];
+
+/// Stores every task as a row tagged with the kind and period of its list.
+pub struct Sqlite {
+    conn: Connection,
+    /// Path of the database file, lock files are kept next to it
+    path: PathBuf,
+}
+
+impl Sqlite {
+    /// Opens the database, creating it and its tables if needed.
+    pub fn open(path: &Path) -> Result<Self, PaskError> {
+        let conn = Connection::open(path).map_err(|e| db_error(path, e))?;
+        migrate(&conn, path)?;
+
+        Ok(Self { conn, path: path.to_path_buf() })
+    }
+
+    /// Gets the directory lock files are kept under, the one the database is in.
+    fn lock_dir(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new("."))
+    }
+
+    fn error(&self, source: rusqlite::Error) -> PaskError {
+        db_error(&self.path, source)
+    }
+}
+
+impl Storage for Sqlite {
+    fn load(&self, name: &str) -> Result<Tasks, PaskError> {
+        let (kind, period) = split_name(name);
+        let mut stmt = self.conn.prepare(
+            "SELECT desc, start_time, end_time, status, task_id, due, deferrals, priority, tags, project FROM tasks
+             WHERE list_kind = ?1 AND period = ?2 ORDER BY position",
+        ).map_err(|e| self.error(e))?;
+
+        let rows = stmt.query_map(params![kind, period], |row| {
+            Ok(Task {
+                desc: row.get(0)?,
+                start_time: row.get::<_, Option<String>>(1)?.and_then(|x| time_from_sql(&x)),
+                end_time: row.get::<_, Option<String>>(2)?.and_then(|x| time_from_sql(&x)),
+                status: Status::from_name(&row.get::<_, String>(3)?).unwrap_or(Status::Todo),
+                id: row.get(4)?,
+                due: row.get::<_, Option<String>>(5)?.and_then(|x| x.parse().ok()),
+                deferrals: row.get(6)?,
+                priority: row.get::<_, Option<String>>(7)?.and_then(|x| Priority::from_name(&x)),
+                // Tags are single words so they are kept separated by spaces
+                tags: row.get::<_, String>(8)?.split_whitespace().map(str::to_owned).collect(),
+                project: row.get(9)?,
+            })
+        }).map_err(|e| self.error(e))?;
+
+        let tasks = rows.collect::<Result<Vec<Task>, _>>().map_err(|e| self.error(e))?;
+        Ok(Tasks { tasks })
+    }
+
+    fn save(&self, name: &str, tasks: &Tasks) -> Result<(), PaskError> {
+        let (kind, period) = split_name(name);
+        let tx = self.conn.unchecked_transaction().map_err(|e| self.error(e))?;
+
+        // Replace the whole list so removed tasks do not linger, and remember it exists even when
+        // it is empty
+        tx.execute("DELETE FROM tasks WHERE list_kind = ?1 AND period = ?2", params![kind, period])
+            .map_err(|e| self.error(e))?;
+        tx.execute("INSERT OR IGNORE INTO lists (list_kind, period) VALUES (?1, ?2)", params![kind, period])
+            .map_err(|e| self.error(e))?;
+        for (position, task) in tasks.tasks.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO tasks (list_kind, period, position, desc, start_time, end_time, status, task_id, due, deferrals, priority, tags, project)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                params![
+                    kind,
+                    period,
+                    position as i64,
+                    task.desc,
+                    task.start_time.map(time_to_sql),
+                    task.end_time.map(time_to_sql),
+                    task.status.name(),
+                    task.id,
+                    task.due.map(|x| x.to_string()),
+                    task.deferrals,
+                    task.priority.map(Priority::name),
+                    task.tags.join(" "),
+                    task.project,
+                ],
+            ).map_err(|e| self.error(e))?;
+        }
+
+        tx.commit().map_err(|e| self.error(e))
+    }
+
+    fn lists(&self) -> Result<Vec<String>, PaskError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT list_kind, period FROM lists ORDER BY list_kind, period",
+        ).map_err(|e| self.error(e))?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(join_name(&row.get::<_, String>(0)?, &row.get::<_, String>(1)?))
+        }).map_err(|e| self.error(e))?;
+
+        rows.collect::<Result<Vec<String>, _>>().map_err(|e| self.error(e))
+    }
+
+    fn delete(&self, name: &str) -> Result<(), PaskError> {
+        let (kind, period) = split_name(name);
+        let tx = self.conn.unchecked_transaction().map_err(|e| self.error(e))?;
+        for table in ["tasks", "lists"] {
+            tx.execute(
+                &format!("DELETE FROM {} WHERE list_kind = ?1 AND period = ?2", table),
+                params![kind, period],
+            ).map_err(|e| self.error(e))?;
+        }
+        tx.commit().map_err(|e| self.error(e))?;
+        ListLock::remove(self.lock_dir(), name)
+    }
+
+    fn lock(&self, name: &str) -> Result<ListLock, PaskError> {
+        ListLock::acquire(self.lock_dir(), name)
+    }
+}
+
+/// Brings the database schema up to the latest version.
+fn migrate(conn: &Connection, path: &Path) -> Result<(), PaskError> {
+    let version: u64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| db_error(path, e))?;
+    if version > MIGRATIONS.len() as u64 {
+        return Err(PaskError::Version {
+            path: path.to_path_buf(),
+            found: version,
+            supported: MIGRATIONS.len() as u64,
+        });
+    }
+
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let step = || -> rusqlite::Result<()> {
+            let tx = conn.unchecked_transaction()?;
+            tx.execute_batch(migration)?;
+            tx.pragma_update(None, "user_version", i + 1)?;
+            tx.commit()
+        };
+        step().map_err(|e| db_error(path, e))?;
+    }
+    Ok(())
+}
+
+/// Splits a list name such as `2026-10-18-day` into its kind and period.
+fn split_name(name: &str) -> (&str, &str) {
+    match name.rsplit_once('-') {
+        Some((period, kind)) => (kind, period),
+        None => (name, ""),
+    }
+}
+
+/// Inverse of `split_name`.
+fn join_name(kind: &str, period: &str) -> String {
+    if period.is_empty() {
+        kind.to_owned()
+    } else {
+        format!("{}-{}", period, kind)
+    }
+}
+
+/// Times are kept as `HH:MM` text so they sort and read naturally in queries.
+fn time_to_sql(time: TimeOfDay) -> String {
+    time.to_string()
+}
+
+fn time_from_sql(text: &str) -> Option<TimeOfDay> {
+    text.parse().ok()
+}
+
+fn db_error(path: &Path, source: rusqlite::Error) -> PaskError {
+    PaskError::Database { path: path.to_path_buf(), source }
+}
+
+/// Copies every dated, goals and named json list from `from` into the database. Lists the
+/// database already has are left alone, they may have changed since the json files were imported.
+///
+/// Returns the number of lists that were imported and the number that were skipped.
+pub fn import_json(from: &dyn Storage, to: &Sqlite) -> Result<(usize, usize), PaskError> {
+    let existing = to.lists()?;
+    let (mut count, mut skipped) = (0, 0);
+    for name in from.lists()? {
+        let is_list = name == "goals"
+            || name.ends_with("-day")
+            || name.ends_with("-week")
+            || name.ends_with("-month")
+            || name.ends_with("-quarter")
+            || name.ends_with("-year")
+            || name.ends_with("-list");
+        if !is_list {
+            continue;
+        }
+
+        if existing.contains(&name) {
+            skipped += 1;
+            continue;
+        }
+
+        // Both keep their lock files in the data directory, so this locks the json list as well
+        let _lock = to.lock(&name)?;
+        to.save(&name, &from.load(&name)?)?;
+        count += 1;
+    }
+    Ok((count, skipped))
+}
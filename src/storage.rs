@@ -0,0 +1,265 @@
+// Backends that task lists can be loaded from and saved to
+use std::{fs, io};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use fs2::FileExt;
+
+use crate::error::PaskError;
+use crate::schema;
+use crate::tasks::Tasks;
+
+/// A place where named task lists are kept.
+pub trait Storage {
+    /// Loads a list, returns an empty list if it has never been saved.
+    fn load(&self, name: &str) -> Result<Tasks, PaskError>;
+    /// Saves a list replacing whatever was stored under the name before.
+    fn save(&self, name: &str, tasks: &Tasks) -> Result<(), PaskError>;
+    /// Returns the names of every list that has been saved.
+    fn lists(&self) -> Result<Vec<String>, PaskError>;
+    /// Removes a list and its lock file, does nothing if it does not exist. The lock on the list
+    /// should be held while deleting it.
+    fn delete(&self, name: &str) -> Result<(), PaskError>;
+    /// Takes an exclusive lock on a list so no other process can change it until the lock is
+    /// dropped. Fails with `PaskError::Locked` if another process already holds it.
+    fn lock(&self, name: &str) -> Result<ListLock, PaskError>;
+}
+
+/// Directory inside the data directory that lock files are kept in, so they do not sit next to
+/// every list.
+const LOCK_DIR: &str = ".locks";
+
+/// Advisory lock on a single list, released when dropped.
+pub struct ListLock {
+    // None for stores that only live in this process
+    file: Option<File>,
+}
+
+impl ListLock {
+    /// Locks the list with the given name using a lock file under `dir`.
+    pub fn acquire(dir: &Path, name: &str) -> Result<Self, PaskError> {
+        let lock_dir = dir.join(LOCK_DIR);
+        fs::create_dir_all(&lock_dir).map_err(|e| PaskError::io(&lock_dir, e))?;
+        let path = lock_path(dir, name);
+
+        loop {
+            let file = fs::OpenOptions::new().create(true).truncate(false).write(true).open(&path)
+                .map_err(|e| PaskError::io(&path, e))?;
+
+            match file.try_lock_exclusive() {
+                // The list may have been deleted along with its lock file between opening and
+                // locking, in which case the lock is on a file nobody else will open
+                Ok(()) if !path.exists() => continue,
+                Ok(()) => return Ok(Self { file: Some(file) }),
+                Err(e) if e.kind() == fs2::lock_contended_error().kind() => {
+                    return Err(PaskError::Locked(name.to_owned()));
+                },
+                Err(e) => return Err(PaskError::io(&path, e)),
+            }
+        }
+    }
+
+    /// Removes the lock file of a list that has been deleted, does nothing if there is none.
+    pub fn remove(dir: &Path, name: &str) -> Result<(), PaskError> {
+        let path = lock_path(dir, name);
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            x => x.map_err(|e| PaskError::io(&path, e)),
+        }
+    }
+}
+
+/// Gets the path to the lock file of a list.
+fn lock_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(LOCK_DIR).join(name.to_owned() + ".lock")
+}
+
+impl Drop for ListLock {
+    fn drop(&mut self) {
+        if let Some(file) = &self.file {
+            let _ = file.unlock();
+        }
+    }
+}
+
+/// Replaces the file at `path` with `content` so that a crash leaves either the old or the new
+/// file but never a truncated one.
+pub fn write_atomic(path: &Path, content: &[u8]) -> Result<(), PaskError> {
+    write_atomic_io(path, content).map_err(|e| PaskError::io(path, e))
+}
+
+fn write_atomic_io(path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+
+    // Write the content to a temporary file and make sure it reached the disk
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&tmp_path, path)?;
+
+    // Make sure the rename itself is durable
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        File::open(dir)?.sync_all()?;
+    }
+
+    Ok(())
+}
+
+/// Opens the storage backend used for the given data directory.
+///
+/// When built with the `sqlite` feature a `pask.db` in the directory takes precedence over the
+/// json files.
+pub fn open(data_dir: &Path) -> Result<Box<dyn Storage>, PaskError> {
+    #[cfg(feature = "sqlite")]
+    {
+        let db = data_dir.join(crate::sqlite::DB_NAME);
+        if db.exists() {
+            return Ok(Box::new(crate::sqlite::Sqlite::open(&db)?));
+        }
+    }
+
+    Ok(Box::new(JsonDir::new(data_dir)))
+}
+
+/// Stores every list as its own json file inside a directory.
+pub struct JsonDir {
+    dir: PathBuf,
+}
+
+impl JsonDir {
+    pub fn new(dir: &Path) -> Self {
+        Self { dir: dir.to_path_buf() }
+    }
+
+    /// Gets the path to the file a list is kept in.
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name.to_owned() + ".json")
+    }
+}
+
+impl Storage for JsonDir {
+    fn load(&self, name: &str) -> Result<Tasks, PaskError> {
+        let path = self.path(name);
+
+        // Get file content into a string
+        let file_content = match fs::read_to_string(&path) {
+            Ok(x) => x,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Tasks { tasks: vec![] }),
+            Err(e) => return Err(PaskError::io(&path, e)),
+        };
+
+        let mut value: serde_json::Value = serde_json::from_str(&file_content)
+            .map_err(|e| PaskError::parse(&path, e))?;
+
+        // Bring lists written by older versions up to date
+        let version = schema::upgrade(&mut value).map_err(|found| PaskError::Version {
+            path: path.clone(),
+            found,
+            supported: schema::CURRENT_VERSION,
+        })?;
+        if version == schema::CURRENT_VERSION {
+            // Going through the text again keeps the line and column of any task that is wrong
+            return serde_json::from_str(&file_content).map_err(|e| PaskError::parse(&path, e));
+        }
+
+        // Keep a copy of the original before writing it in the current format
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(format!(".v{}.bak", version));
+        write_atomic(Path::new(&backup), file_content.as_bytes())?;
+
+        // Only write the upgraded list while holding its lock and if nobody changed it since
+        // it was read. If the lock is taken, by this process or another, whoever holds it
+        // saves the list in the current format anyway
+        match self.lock(name) {
+            Ok(_lock) => {
+                let current = fs::read_to_string(&path).map_err(|e| PaskError::io(&path, e))?;
+                if current == file_content {
+                    let upgraded = serde_json::to_string(&value).map_err(|e| PaskError::parse(&path, e))?;
+                    write_atomic(&path, upgraded.as_bytes())?;
+                }
+            },
+            Err(PaskError::Locked(_)) => {},
+            Err(e) => return Err(e),
+        }
+
+        // Deseralize content into task struct and return, an upgraded value has no positions
+        serde_json::from_value(value).map_err(|e| PaskError::parse(&path, e))
+    }
+
+    fn save(&self, name: &str, tasks: &Tasks) -> Result<(), PaskError> {
+        let path = self.path(name);
+
+        // Seralize struct into json
+        let file_content = serde_json::to_string(&schema::Versioned::new(tasks))
+            .map_err(|e| PaskError::parse(&path, e))?;
+
+        write_atomic(&path, file_content.as_bytes())
+    }
+
+    fn lists(&self) -> Result<Vec<String>, PaskError> {
+        let mut names = vec![];
+        let entries = fs::read_dir(&self.dir).map_err(|e| PaskError::io(&self.dir, e))?;
+        for entry in entries {
+            let path = entry.map_err(|e| PaskError::io(&self.dir, e))?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                if let Some(stem) = path.file_stem().and_then(|x| x.to_str()) {
+                    names.push(stem.to_owned());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    fn delete(&self, name: &str) -> Result<(), PaskError> {
+        let path = self.path(name);
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {},
+            x => x.map_err(|e| PaskError::io(&path, e))?,
+        }
+        ListLock::remove(&self.dir, name)
+    }
+
+    fn lock(&self, name: &str) -> Result<ListLock, PaskError> {
+        ListLock::acquire(&self.dir, name)
+    }
+}
+
+/// Keeps lists in memory so the code working on a `Storage` can be tested without files.
+/// Nothing else can reach the lists, so locks are always granted.
+#[cfg(test)]
+#[derive(Default)]
+pub struct Memory {
+    lists: std::cell::RefCell<std::collections::BTreeMap<String, Vec<crate::tasks::Task>>>,
+}
+
+#[cfg(test)]
+impl Storage for Memory {
+    fn load(&self, name: &str) -> Result<Tasks, PaskError> {
+        Ok(Tasks { tasks: self.lists.borrow().get(name).cloned().unwrap_or_default() })
+    }
+
+    fn save(&self, name: &str, tasks: &Tasks) -> Result<(), PaskError> {
+        self.lists.borrow_mut().insert(name.to_owned(), tasks.tasks.clone());
+        Ok(())
+    }
+
+    fn lists(&self) -> Result<Vec<String>, PaskError> {
+        Ok(self.lists.borrow().keys().cloned().collect())
+    }
+
+    fn delete(&self, name: &str) -> Result<(), PaskError> {
+        self.lists.borrow_mut().remove(name);
+        Ok(())
+    }
+
+    fn lock(&self, _name: &str) -> Result<ListLock, PaskError> {
+        Ok(ListLock { file: None })
+    }
+}
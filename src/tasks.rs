@@ -1,9 +1,181 @@
-use std::{fmt, fs, path::Path};
+use std::fmt;
 use std::cmp::Ordering;
 
+use chrono::{NaiveDate, NaiveDateTime};
+use clap::ValueEnum;
 use serde::{Serialize, Deserialize};
-use serde_json;
-use crate::cl::Add;
+use crate::cl::{Add, Edit};
+use crate::date::{DateContext, Due};
+use crate::error::PaskError;
+use crate::filter::{Filter, Label};
+use crate::storage::Storage;
+use crate::time::TimeOfDay;
+
+/// Time format used when none is configured.
+pub const DEFAULT_TIME_FORMAT: &str = "%H:%M";
+
+/// Where a task is in its life.
+#[derive(Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum Status {
+    Todo,
+    InProgress,
+    Blocked,
+    Done,
+    Cancelled,
+    /// Copied into a later list by a rollover
+    CarriedOver,
+}
+
+impl Status {
+    /// Box shown in front of a task with this status.
+    pub fn marker(self) -> &'static str {
+        match self {
+            Status::Todo => "[ ]",
+            Status::InProgress => "[>]",
+            Status::Blocked => "[!]",
+            Status::Done => "[x]",
+            Status::Cancelled => "[-]",
+            Status::CarriedOver => "[~]",
+        }
+    }
+
+    /// Gets the name the status is saved with.
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::Todo => "todo",
+            Status::InProgress => "in-progress",
+            Status::Blocked => "blocked",
+            Status::Done => "done",
+            Status::Cancelled => "cancelled",
+            Status::CarriedOver => "carried-over",
+        }
+    }
+
+    /// Inverse of `name`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Status::Todo, Status::InProgress, Status::Blocked, Status::Done, Status::Cancelled, Status::CarriedOver]
+            .into_iter()
+            .find(|x| x.name() == name)
+    }
+}
+
+/// How important a task is, high priority tasks come first among tasks without times.
+#[derive(Serialize, Deserialize, ValueEnum)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum Priority {
+    #[value(alias = "h")]
+    High,
+    #[value(alias = "m")]
+    Medium,
+    #[value(alias = "l")]
+    Low,
+}
+
+impl Priority {
+    /// Shown in front of the desc of a task with this priority.
+    pub fn marker(self) -> &'static str {
+        match self {
+            Priority::High => "!!!",
+            Priority::Medium => "!!",
+            Priority::Low => "!",
+        }
+    }
+
+    /// Gets the name the priority is saved with.
+    pub fn name(self) -> &'static str {
+        match self {
+            Priority::High => "high",
+            Priority::Medium => "medium",
+            Priority::Low => "low",
+        }
+    }
+
+    /// Inverse of `name`.
+    #[cfg_attr(not(feature = "sqlite"), allow(dead_code))]
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Priority::High, Priority::Medium, Priority::Low].into_iter().find(|x| x.name() == name)
+    }
+
+    /// Gets the priority after this one when cycling through them in the interface, no priority
+    /// comes after low.
+    pub fn cycle(priority: Option<Self>) -> Option<Self> {
+        match priority {
+            None => Some(Priority::High),
+            Some(Priority::High) => Some(Priority::Medium),
+            Some(Priority::Medium) => Some(Priority::Low),
+            Some(Priority::Low) => None,
+        }
+    }
+}
+
+/// What lists are sorted by. Ties are broken by the other keys, and tasks that are still equal
+/// keep the order they were added in.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SortKey {
+    /// Timed tasks by start time first, then untimed tasks by priority
+    Time,
+    /// Priority first, then start time
+    Priority,
+    /// Due date first, then start time and priority
+    Due,
+}
+
+impl SortKey {
+    /// Gets the name the sort key is configured with.
+    pub fn name(self) -> &'static str {
+        match self {
+            SortKey::Time => "time",
+            SortKey::Priority => "priority",
+            SortKey::Due => "due",
+        }
+    }
+
+    /// Inverse of `name`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        [SortKey::Time, SortKey::Priority, SortKey::Due].into_iter().find(|x| x.name() == name)
+    }
+
+    /// Compares two tasks by this key.
+    pub fn compare(self, a: &Task, b: &Task) -> Ordering {
+        let time = || none_last(a.start_time, b.start_time);
+        let priority = || none_last(a.priority, b.priority);
+        // A due date without a time is due by the end of the day
+        let due = |x: &Task| x.due.map(|due| (due.date, due.time.is_none(), due.time));
+        match self {
+            SortKey::Time => time().then_with(priority),
+            SortKey::Priority => priority().then_with(time),
+            SortKey::Due => none_last(due(a), due(b))
+                .then_with(time)
+                .then_with(priority),
+        }
+    }
+}
+
+/// Orders values that are set before values that are not.
+fn none_last<T: Ord>(a: Option<T>, b: Option<T>) -> Ordering {
+    match (a, b) {
+        (Some(x), Some(y)) => x.cmp(&y),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Where a task stands compared to the current time, used to color it.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Timing {
+    /// Has not started yet or has no times
+    Upcoming,
+    /// Between its start and end time, or started by the user
+    InProgress,
+    /// Past its end time or due date and not finished
+    Overdue,
+    /// Done, cancelled or carried over
+    Done,
+}
 
 /// Reresents a signle task item 
 #[derive(Serialize, Deserialize)]
@@ -11,12 +183,24 @@ use crate::cl::Add;
 #[derive(Clone)]
 pub struct Task { /// Description of the task
     pub desc: String,              
-    /// Hour minute represents the time to start the task  
-    pub start_time: Option<(u8, u8)>,
-    /// Hour minute represents the time to end the task
-    pub end_time: Option<(u8, u8)>,
-    /// If the task has been completed or not 
-    pub completed: bool,
+    /// Time to start the task  
+    pub start_time: Option<TimeOfDay>,
+    /// Time to end the task
+    pub end_time: Option<TimeOfDay>,
+    /// When the task has to be done by
+    pub due: Option<Due>,
+    /// Where the task is in its life
+    pub status: Status,
+    /// How important the task is
+    pub priority: Option<Priority>,
+    /// Words the task is tagged with, written `+tag` in a desc
+    pub tags: Vec<String>,
+    /// Project the task belongs to, written `project:name` in a desc
+    pub project: Option<String>,
+    /// How many times the task has been carried over into a later list
+    pub deferrals: u32,
+    /// Unique id that stays with the task for its whole life
+    pub id: String,
 
 }
     
@@ -27,77 +211,162 @@ impl Task {
             desc: String::new(), 
             start_time: None, 
             end_time: None, 
-            completed: false,
+            due: None,
+            status: Status::Todo,
+            priority: None,
+            tags: vec![],
+            project: None,
+            deferrals: 0,
+            id: new_id(),
         }
     }
 
     /// Takes in a add struct and returns the struct representing the task.
-    pub fn from_add(add: Add) -> Result<Self, &'static str> {
+    pub fn from_add(add: Add, context: DateContext) -> Result<Self, PaskError> {
         // Parse a string in to hours and minutes. While also making sure it is a valid string 
-        let start_time: Option<(u8, u8)> = match add.start_time {
-            Some(x) => {
-                match string_to_time(x) {
-                    Ok(x) => Some(x),
-                    Err(x) => return Err(x),
-                } 
-            },
-            None => None,
-        };
-        let end_time: Option<(u8, u8)> = match add.end_time {
-            Some(x) => {
-                match string_to_time(x) {
-                    Ok(x) => Some(x), 
-                    Err(x) => return Err(x),
-                }
-            },
-            None => None,
-        };
-        Ok(Task { 
+        let start_time = add.start_time.map(string_to_time).transpose()?;
+        let end_time = add.end_time.map(string_to_time).transpose()?;
+        check_times(start_time, end_time)?;
+        let due = add.due.map(|x| Due::parse(&x, context)).transpose().map_err(PaskError::Validation)?;
+
+        let mut task = Task { 
             desc: add.desc,
             start_time,
             end_time,
-            completed: false,
-        })
+            due,
+            status: Status::Todo,
+            priority: add.priority,
+            tags: vec![],
+            project: None,
+            deferrals: 0,
+            id: new_id(),
+        };
+
+        // The flags win over what is written in the desc
+        task.take_labels();
+        for tag in add.tags {
+            task.add_tag(tag);
+        }
+        if add.project.is_some() {
+            task.project = add.project;
+        }
+        Ok(task)
+    }
+
+    /// Moves the `+tag` and `project:name` words of the desc into the tags and project of the
+    /// task. Descs without any are left as they were typed.
+    pub fn take_labels(&mut self) {
+        let mut words = vec![];
+        let mut found = false;
+        for word in self.desc.clone().split_whitespace() {
+            match Label::parse(word) {
+                Some(Label::Tag(tag)) => self.add_tag(tag),
+                Some(Label::Project(project)) => self.project = Some(project),
+                None => {
+                    words.push(word.to_owned());
+                    continue;
+                },
+            }
+            found = true;
+        }
+        if found {
+            self.desc = words.join(" ");
+        }
+    }
+
+    /// Tags the task, unless it already has the tag.
+    fn add_tag(&mut self, tag: String) {
+        if !self.tags.contains(&tag) {
+            self.tags.push(tag);
+        }
+    }
+
+    /// Works out where the task stands at the given time, with its start and end times on `day`.
+    pub fn timing(&self, now: NaiveDateTime, day: NaiveDate) -> Timing {
+        if matches!(self.status, Status::Done | Status::Cancelled | Status::CarriedOver) {
+            return Timing::Done;
+        }
+
+        let time = |x: TimeOfDay| day.and_time(x.to_naive());
+        let past_due = self.due.is_some_and(|due| match due.time {
+            Some(due_time) => due.date.and_time(due_time.to_naive()) < now,
+            None => due.date < now.date(),
+        });
+        if past_due || self.end_time.is_some_and(|end| time(end) < now) {
+            Timing::Overdue
+        } else if self.status == Status::InProgress
+            || self.start_time.is_some_and(|start| time(start) <= now) {
+            Timing::InProgress
+        } else {
+            Timing::Upcoming
+        }
+    }
+
+    /// Whether the task still has to be done, and so should be carried over by a rollover.
+    pub fn is_unfinished(&self) -> bool {
+        matches!(self.status, Status::Todo | Status::InProgress | Status::Blocked)
+    }
+
+    /// Changes the fields given in an edit struct, the id and status are kept.
+    pub fn apply_edit(&mut self, edit: Edit, context: DateContext) -> Result<(), PaskError> {
+        // Parse everything first so a bad time leaves the task untouched
+        let start_time = edit.start.map(string_to_time).transpose()?;
+        let end_time = edit.end.map(string_to_time).transpose()?;
+        let due = edit.due.map(|x| Due::parse(&x, context)).transpose().map_err(PaskError::Validation)?;
+
+        let start_time = if edit.clear_start { None } else { start_time.or(self.start_time) };
+        let end_time = if edit.clear_end { None } else { end_time.or(self.end_time) };
+        check_times(start_time, end_time)?;
+
+        if let Some(desc) = edit.desc {
+            self.desc = desc;
+            self.take_labels();
+        }
+        self.start_time = start_time;
+        self.end_time = end_time;
+        if edit.clear_due {
+            self.due = None;
+        }
+        if due.is_some() {
+            self.due = due;
+        }
+        if edit.clear_priority {
+            self.priority = None;
+        }
+        if edit.priority.is_some() {
+            self.priority = edit.priority;
+        }
+        self.tags.retain(|x| !edit.untag.contains(x));
+        for tag in edit.tags {
+            self.add_tag(tag);
+        }
+        if edit.clear_project {
+            self.project = None;
+        }
+        if edit.project.is_some() {
+            self.project = edit.project;
+        }
+        Ok(())
     }
 }    
 
 // Implement ordering for the task 
 impl PartialEq for Task {
     fn eq(&self, other: &Self) -> bool {
-        if self.start_time.is_some() && other.start_time.is_some() {
-            self.start_time.unwrap().0 == other.start_time.unwrap().0 && 
-                self.start_time.unwrap().1 == other.start_time.unwrap().1
-        } else if self.start_time.is_some() {
-            false
-        } else if other.start_time.is_some() {
-            false
-        } else {
-            true
-        }
+        self.cmp(other) == Ordering::Equal
     }
 }
 
 impl PartialOrd for Task {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(&other))
+        Some(self.cmp(other))
     }
 }
 
+/// Tasks are ordered by the default sort key
 impl Ord for Task {
     fn cmp(&self, other: &Self) -> Ordering {
-        if self.start_time.is_some() && other.start_time.is_some() {
-            if self.start_time.unwrap().0 != other.start_time.unwrap().0 {
-                self.start_time.unwrap().0.cmp(&other.start_time.unwrap().0)
-            } else {
-                self.start_time.unwrap().1.cmp(&other.start_time.unwrap().1)
-            }
-        } else if self.start_time.is_some() {
-            Ordering::Less
-        } else if other.start_time.is_some() {
-            Ordering::Greater
-        } else {
-            Ordering::Equal
-        }
+        SortKey::Time.compare(self, other)
     }
 }
 
@@ -105,23 +374,40 @@ impl Ord for Task {
 // Implement the ability to display tasks.
 impl fmt::Display for Task {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let is_completed = if self.completed {
-            "[x]"
-        } else {
-            "[ ]"
+        write!(f, "{}", self.format(DEFAULT_TIME_FORMAT))
+    }
+}
+
+impl Task {
+    /// Formats the task showing its times with a strftime style format.
+    pub fn format(&self, time_format: &str) -> String {
+        let is_completed = match self.priority {
+            Some(priority) => format!("{} {}", self.status.marker(), priority.marker()),
+            None => self.status.marker().to_owned(),
         };
-        
-        if self.start_time.is_some() && self.end_time.is_some() {
-            write!(f,"{} {} {:02}:{:02} - {:02}:{:02}", is_completed, self.desc, self.start_time.unwrap().0, 
-                self.start_time.unwrap().1, self.end_time.unwrap().0, self.end_time.unwrap().1)?;
-        } else if self.start_time.is_some() {
-            write!(f,"{} {} {:02}:{:02}", is_completed, self.desc, self.start_time.unwrap().0, 
-                self.start_time.unwrap().1)?;
-        } else {
-            write!(f,"{} {}", is_completed, self.desc)?;
+        let time = |x: TimeOfDay| x.format(time_format);
+
+        // Tags and the project are shown the way they are written
+        let mut desc = self.desc.clone();
+        for tag in &self.tags {
+            desc += &format!(" +{}", tag);
+        }
+        if let Some(project) = &self.project {
+            desc += &format!(" project:{}", project);
         }
 
-        Ok(())
+        let mut out = match (self.start_time, self.end_time) {
+            (Some(start), Some(end)) => format!("{} {} {} - {}", is_completed, desc, time(start), time(end)),
+            (Some(start), None) => format!("{} {} {}", is_completed, desc, time(start)),
+            _ => format!("{} {}", is_completed, desc),
+        };
+        if let Some(due) = self.due {
+            out += &format!(" (due {})", due.format(time_format));
+        }
+        if self.deferrals > 0 {
+            out += &format!(" (deferred {}x)", self.deferrals);
+        }
+        out
     }
 }
 
@@ -131,132 +417,282 @@ pub struct Tasks {
 }
 
 impl Tasks {
-    pub fn get_tasks(file_name: &String) -> Result<Self, ()> {
-        // Checks if the file exists 
-        if Path::new(file_name).exists() {
-            Self::read_tasks(file_name)
-        } else {
-            Ok(Self { tasks: vec![] })
-        }
+    /// Loads the list with the given name from the storage backend.
+    pub fn get_tasks(storage: &dyn Storage, name: &str) -> Result<Self, PaskError> {
+        storage.load(name)
     }
 
 
-    /// If write fails will return false
-    pub fn write_tasks(&mut self, file_name: &String) -> bool {
-        // Make sure the tasks are in sorted order by start time
-        // Seralize struct into json 
-        let file_content = match serde_json::to_string(self) {
-            Ok(x) => x,
-            Err(_) => return false,
-        };
-
-        fs::write(file_name, file_content).unwrap();
-
-        return true;
+    /// Saves the list back to the storage backend.
+    pub fn write_tasks(&mut self, storage: &dyn Storage, name: &str) -> Result<(), PaskError> {
+        storage.save(name, self)
     }
     
     /// Add a tasks to the set of tasks 
-    pub fn add_task(&mut self, task: Task) {
-        // make sure the task desc is not already contained within the list. If so append with a
-        // number
+    pub fn add_task(&mut self, task: Task, sort: SortKey) {
         self.tasks.push(task); 
         
-        // Make sure the list is still sorted by time 
-        self.sort_tasks();
+        // Make sure the list is still sorted
+        self.sort(sort);
     }
 
-    pub fn complete_task(&mut self, desc: String) {
-        // Find task 
-        for task in &mut self.tasks {
+    /// Finds the index of the task a selector refers to.
+    ///
+    /// The selector can be the display index of the task, its id, a prefix of its id that only one
+    /// task has, or its exact description. Numbers are indexes when the list has a task there and
+    /// ids otherwise, `id:` in front of the selector only ever matches ids.
+    pub fn find_task(&self, selector: &str) -> Result<usize, PaskError> {
+        let (selector, only_ids) = match selector.strip_prefix("id:") {
+            Some(id) => (id, true),
+            None => (selector, false),
+        };
+
+        // Every id starts with an empty prefix, so nothing would stop it from picking a task
+        if selector.trim().is_empty() {
+            return Err(PaskError::Validation("Give the index, id or desc of a task".to_owned()));
+        }
+
+        // Display indexes start from 1
+        let is_number = selector.chars().all(|c| c.is_ascii_digit());
+        if is_number && !only_ids {
+            if let Ok(x) = selector.parse::<usize>() {
+                if x >= 1 && x <= self.tasks.len() {
+                    return Ok(x - 1);
+                }
+            }
+        }
 
-            if task.desc == desc {
-                task.completed = true;
-                return;
+        let checks: [&dyn Fn(&Task) -> bool; 3] = [
+            &|task| task.id == selector,
+            &|task| task.id.starts_with(selector),
+            &|task| task.desc == selector,
+        ];
+        let checks = if only_ids { &checks[..2] } else { &checks[..] };
+        for check in checks {
+            let matches: Vec<usize> = (0..self.tasks.len()).filter(|&i| check(&self.tasks[i])).collect();
+            match matches.len() {
+                0 => continue,
+                1 => return Ok(matches[0]),
+                _ => {
+                    let found: Vec<String> = matches.iter()
+                        .map(|&i| format!("  {} {}", short_id(&self.tasks[i].id), self.tasks[i].desc))
+                        .collect();
+                    return Err(PaskError::Validation(format!(
+                        "{} matches several tasks, use the index or more of the id:\n{}", selector, found.join("\n")
+                    )));
+                },
             }
         }
+
+        if is_number && !only_ids {
+            return Err(PaskError::Validation(format!(
+                "There is no task {}, the list has {} tasks", selector, self.tasks.len()
+            )));
+        }
+        Err(PaskError::Validation(format!("No task matches {}", selector)))
     }
 
+    pub fn set_status(&mut self, selector: &str, status: Status) -> Result<(), PaskError> {
+        let idx = self.find_task(selector)?;
+        self.tasks[idx].status = status;
+        Ok(())
+    }
+
+    /// Marks a task as done, or reopens it if it already was.
     pub fn complete_task_idx(&mut self, idx: usize) {
-        self.tasks[idx].completed = match self.tasks[idx].completed {
-            true => false,
-            false => true,
+        self.tasks[idx].status = match self.tasks[idx].status {
+            Status::Done => Status::Todo,
+            _ => Status::Done,
         };
     }
 
-    pub fn del_task(&mut self, desc: String) {
-        let mut count = 0;
+    /// Edits a task in place and moves it to where it now belongs in the list.
+    pub fn edit_task(&mut self, edit: Edit, context: DateContext, sort: SortKey) -> Result<(), PaskError> {
+        let idx = self.find_task(&edit.task)?;
+        self.tasks[idx].apply_edit(edit, context)?;
+        self.sort(sort);
+        Ok(())
+    }
 
-        let mut index: usize = 0;
-        let mut remove = false;
-        for task in &self.tasks {
-            
-            if task.desc == desc {
-                index = count;
-                remove = true;
-            }
-            count += 1;
-        }
-        if remove {
-            self.tasks.remove(index);
-        }
+    pub fn del_task(&mut self, selector: &str) -> Result<(), PaskError> {
+        let idx = self.find_task(selector)?;
+        self.tasks.remove(idx);
+        Ok(())
     }
 
-    pub fn del_task_idx(&mut self, idx: usize) {
+    /// Moves a task into the list saved under `to`, which is locked, loaded and saved here.
+    pub fn move_task(&mut self, selector: &str, storage: &dyn Storage, to: &str, sort: SortKey) -> Result<(), PaskError> {
+        let idx = self.find_task(selector)?;
+        self.move_task_idx(idx, storage, to, sort)
+    }
+
+    /// Moves the task at an index into the list saved under `to`.
+    ///
+    /// A task with the same id already in the other list is replaced. The task is only removed
+    /// from this list once the other list is saved, and this list is saved by the caller after,
+    /// so a crash in between leaves the task in both lists rather than in neither.
+    pub fn move_task_idx(&mut self, idx: usize, storage: &dyn Storage, to: &str, sort: SortKey) -> Result<(), PaskError> {
+        let _lock = storage.lock(to)?;
+        let mut target = storage.load(to)?;
+
+        // The other list can already have the task, such as the original a rollover copied it from.
+        // The task being moved is the one in use so it takes the place of the old entry
+        let task = self.tasks[idx].clone();
+        target.tasks.retain(|x| x.id != task.id);
+        target.add_task(task, sort);
+        storage.save(to, &target)?;
+
         self.tasks.remove(idx);
+        Ok(())
     }
 
-    // Sorts the tasks by start time will be implemented soon 
-    fn sort_tasks(&mut self) {
-        self.tasks.sort();
+    pub fn del_task_idx(&mut self, idx: usize) {
+        self.tasks.remove(idx);
     }
-    
-    /// Will return none if file does not exist or if a parsing error occurs.
-    fn read_tasks(file_name: &String) -> Result<Self, ()> {
-        // Get file content into a string 
-        let file_content = match fs::read_to_string(file_name) {
-            Ok(x) => x,
-            Err(_)=> return Err(()),
-        };
-        
-        // Deseralize content into task struct and reutrn 
-        let tasks: Self = match serde_json::from_str(&file_content) {
-            Ok(x) => x,
-            Err(_) => return Err(()),
-        };
-        Ok(tasks)
+
+    /// Sorts the tasks by the given key, keeping the order of tasks it can not tell apart.
+    pub fn sort(&mut self, key: SortKey) {
+        self.tasks.sort_by(|a, b| key.compare(a, b));
     }
 }
 
 impl fmt::Display for Tasks {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for item in &self.tasks {
-            writeln!(f, "{}", item)?;
-        } 
-        Ok(())
+        write!(f, "{}", self.format(DEFAULT_TIME_FORMAT))
     }
 }
 
-/// String to time in format hour, minutes.
-fn string_to_time(string: String) -> Result<(u8, u8), &'static str> {
-    let str_split: Vec<&str> = string.split(":").collect();
-    if str_split.len() != 2 {
-        return Err("Input should be in format hour::minutes");
+impl Tasks {
+    /// Counts the done tasks and the tasks that count towards the list, cancelled and carried over
+    /// tasks do not count.
+    pub fn progress(&self) -> (usize, usize) {
+        let counted = self.tasks.iter().filter(|x| !matches!(x.status, Status::Cancelled | Status::CarriedOver));
+        let (done, total) = counted.fold((0, 0), |(done, total), x| {
+            (done + usize::from(x.status == Status::Done), total + 1)
+        });
+        (done, total)
     }
-    if str_split[0].len() != 1 && str_split[0].len() != 2 {
-        return Err("Number of digits for hours is incorrect");
+
+    /// Formats every task on its own line, starting with the index and short id used to refer to it.
+    pub fn format(&self, time_format: &str) -> String {
+        self.format_with(time_format, &Filter::default(), |_, line| line)
     }
-    if str_split[1].len() != 1 && str_split[1].len() != 2 {
-        return Err("Number of digits for minutes is incorrect");
+
+    /// Formats the tasks that match `filter` like `format`, keeping their indexes in the whole
+    /// list, and passes each task and its line through `paint` first.
+    pub fn format_with<F>(&self, time_format: &str, filter: &Filter, paint: F) -> String
+    where
+        F: Fn(&Task, String) -> String,
+    {
+        let width = self.tasks.len().to_string().len();
+        let mut out = String::new();
+        for (idx, item) in self.tasks.iter().enumerate().filter(|(_, x)| filter.matches(x)) {
+            let line = format!(
+                "{:>width$} {} {}", idx + 1, short_id(&item.id), item.format(time_format), width = width
+            );
+            out += &paint(item, line);
+            out.push('\n');
+        }
+        out
     }
+}
 
-    let hour: u8 = match str_split[0].parse() {
-        Ok(x) => x, 
-        Err(_) => return Err("Hours does not contain all numbers"),
-    };
-    let minute: u8 = match str_split[1].parse() {
-        Ok(x) => x,
-        Err(_) => return Err("Minutes does not contain all numbers"),
-    };
-    
-    Ok((hour, minute))
+/// Parses a time of day given on the command line.
+fn string_to_time(string: String) -> Result<TimeOfDay, PaskError> {
+    string.parse().map_err(PaskError::Validation)
+}
+
+/// Makes sure a task does not end before it starts.
+fn check_times(start: Option<TimeOfDay>, end: Option<TimeOfDay>) -> Result<(), PaskError> {
+    match (start, end) {
+        (Some(start), Some(end)) if end < start => Err(PaskError::Validation(format!(
+            "The end time {} is before the start time {}", end, start
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Creates an id for a new task.
+pub fn new_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// Gets the start of an id, which is usually enough to tell tasks apart.
+pub fn short_id(id: &str) -> &str {
+    &id[..id.len().min(8)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Memory;
+
+    fn list() -> Tasks {
+        let task = |desc: &str, id: &str| Task { desc: desc.to_owned(), id: id.to_owned(), ..Task::new() };
+        Tasks {
+            tasks: vec![
+                task("Standup", "0c1f5e6a2b8d4f3e9a7c6b5d4e3f2a1b"),
+                task("Lunch", "0c1f9a1c3b4e6d8f0a2b4c6d8e0f1a5d"),
+                task("Write report", "12345678a4f3e2d1c0b9a8f7e6d5c4b9"),
+                task("Lunch", "3b2a1f0e9d8c7b6a5f4e3d2c1b0a9f8e"),
+            ],
+        }
+    }
+
+    fn error(result: Result<usize, PaskError>) -> String {
+        match result {
+            Err(PaskError::Validation(message)) => message,
+            other => panic!("expected a validation error, got {:?}", other.map_err(|x| x.to_string())),
+        }
+    }
+
+    #[test]
+    fn selectors_find_tasks() {
+        let tasks = list();
+        assert_eq!(tasks.find_task("1").unwrap(), 0);
+        assert_eq!(tasks.find_task("4").unwrap(), 3);
+        assert_eq!(tasks.find_task("0c1f5e6a2b8d4f3e9a7c6b5d4e3f2a1b").unwrap(), 0);
+        assert_eq!(tasks.find_task("0c1f9").unwrap(), 1);
+        assert_eq!(tasks.find_task("3b2a").unwrap(), 3);
+        assert_eq!(tasks.find_task("Write report").unwrap(), 2);
+
+        // Short ids that are all digits are ids when there is no task at that index
+        assert_eq!(tasks.find_task("12345678").unwrap(), 2);
+        assert_eq!(tasks.find_task("id:12").unwrap(), 2);
+        assert_eq!(tasks.find_task("id:3b2a").unwrap(), 3);
+    }
+
+    #[test]
+    fn bad_selectors_explain_themselves() {
+        let tasks = list();
+        assert!(error(tasks.find_task("0c1f")).starts_with("0c1f matches several tasks"));
+        assert!(error(tasks.find_task("Lunch")).starts_with("Lunch matches several tasks"));
+        assert_eq!(error(tasks.find_task("7")), "There is no task 7, the list has 4 tasks");
+        assert!(error(tasks.find_task("0")).starts_with("0 matches several tasks"));
+        assert_eq!(error(tasks.find_task("99")), "There is no task 99, the list has 4 tasks");
+        assert_eq!(error(tasks.find_task("Dinner")), "No task matches Dinner");
+        assert_eq!(error(tasks.find_task("id:Standup")), "No task matches Standup");
+        for selector in ["", "  ", "id:"] {
+            assert_eq!(error(tasks.find_task(selector)), "Give the index, id or desc of a task");
+        }
+    }
+
+    #[test]
+    fn moving_replaces_a_task_with_the_same_id() {
+        let storage = Memory::default();
+        let mut goals = list();
+        goals.tasks[0].desc = "Old standup".to_owned();
+        goals.tasks.truncate(2);
+        storage.save("goals", &goals).unwrap();
+
+        let mut tasks = list();
+        tasks.move_task_idx(0, &storage, "goals", SortKey::Time).unwrap();
+
+        let descs = |tasks: &Tasks| tasks.tasks.iter().map(|x| x.desc.clone()).collect::<Vec<_>>();
+        assert_eq!(descs(&tasks), ["Lunch", "Write report", "Lunch"]);
+        let goals = storage.load("goals").unwrap();
+        assert_eq!(goals.tasks.len(), 2);
+        assert!(descs(&goals).contains(&"Standup".to_owned()));
+        assert_eq!(goals.tasks.iter().filter(|x| x.id == "0c1f5e6a2b8d4f3e9a7c6b5d4e3f2a1b").count(), 1);
+    }
 }
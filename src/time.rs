@@ -0,0 +1,162 @@
+// Time of day a task starts or ends at
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::NaiveTime;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A valid time of day with minute precision.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TimeOfDay {
+    hour: u8,
+    minute: u8,
+}
+
+impl TimeOfDay {
+    /// Returns none unless the hour is below 24 and the minute below 60.
+    pub fn new(hour: u8, minute: u8) -> Option<Self> {
+        if hour < 24 && minute < 60 {
+            Some(Self { hour, minute })
+        } else {
+            None
+        }
+    }
+
+    pub fn to_naive(self) -> NaiveTime {
+        NaiveTime::from_hms_opt(self.hour.into(), self.minute.into(), 0).unwrap()
+    }
+
+    /// Formats the time with a strftime style format.
+    pub fn format(self, time_format: &str) -> String {
+        self.to_naive().format(time_format).to_string()
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+/// Parses `14:30`, `14h30`, `14h`, `1430`, `930`, `9`, `9am`, `9:30pm` and `9.30 pm`.
+impl FromStr for TimeOfDay {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let lower = string.trim().to_lowercase();
+
+        // Split off am or pm
+        let (text, meridiem) = match lower.strip_suffix("am") {
+            Some(x) => (x.trim_end(), Some(false)),
+            None => match lower.strip_suffix("pm") {
+                Some(x) => (x.trim_end(), Some(true)),
+                None => (lower.as_str(), None),
+            },
+        };
+
+        // Times are only ever ascii, checking first makes the byte offsets below safe to slice at
+        let invalid = || format!("{} should be HH:MM, 9am, 14h30 or 930", string);
+        if !text.is_ascii() {
+            return Err(invalid());
+        }
+
+        let (hour, minute) = match text.find([':', 'h', '.']) {
+            Some(i) => (&text[..i], &text[i + 1..]),
+            // Without a separator the last two digits are the minutes, unless there are only
+            // enough digits for the hour
+            None if text.len() > 2 => text.split_at(text.len() - 2),
+            None => (text, ""),
+        };
+        let minute = if minute.is_empty() && !text.contains(':') { "0" } else { minute };
+
+        let digits = |x: &str, name: &str| -> Result<u8, String> {
+            if x.is_empty() || x.len() > 2 || !x.chars().all(|c| c.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            x.parse().map_err(|_| format!("{} has invalid {}", string, name))
+        };
+        let mut hour = digits(hour, "hours")?;
+        let minute = digits(minute, "minutes")?;
+
+        if let Some(pm) = meridiem {
+            if hour == 0 || hour > 12 {
+                return Err(format!("{}: hours should be from 1 to 12 with am or pm", string));
+            }
+            hour = match (hour, pm) {
+                (12, false) => 0,
+                (12, true) => 12,
+                (x, false) => x,
+                (x, true) => x + 12,
+            };
+        }
+
+        if hour >= 24 {
+            return Err(format!("{}: hours should be below 24", string));
+        }
+        if minute >= 60 {
+            return Err(format!("{}: minutes should be below 60", string));
+        }
+        Ok(Self { hour, minute })
+    }
+}
+
+/// Saved as `HH:MM` text.
+impl Serialize for TimeOfDay {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TimeOfDay {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn times_parse_in_every_form() {
+        let cases = [
+            ("14:30", (14, 30)),
+            ("14h30", (14, 30)),
+            ("14h", (14, 0)),
+            ("14.30", (14, 30)),
+            ("1430", (14, 30)),
+            ("930", (9, 30)),
+            ("0930", (9, 30)),
+            ("9", (9, 0)),
+            ("0", (0, 0)),
+            ("9am", (9, 0)),
+            ("9:30pm", (21, 30)),
+            ("9.30 PM", (21, 30)),
+            ("12am", (0, 0)),
+            ("12:15pm", (12, 15)),
+            (" 23:59 ", (23, 59)),
+        ];
+        for (text, (hour, minute)) in cases {
+            assert_eq!(text.parse(), Ok(TimeOfDay { hour, minute }), "parsing {}", text);
+        }
+    }
+
+    #[test]
+    fn bad_times_are_rejected() {
+        for text in [
+            "", "24:00", "12:60", "9:", "123:00", "12345", "0am", "13pm", "9:3x", "am", "-1",
+            "\u{e9}a", "\u{20ac}", "1\u{e9}30", "\u{e9}\u{e9}\u{e9}", "9:30 \u{e9}m",
+        ] {
+            assert!(text.parse::<TimeOfDay>().is_err(), "{:?} should not parse", text);
+        }
+    }
+
+    #[test]
+    fn times_display_and_round_trip() {
+        let time = TimeOfDay::new(9, 5).unwrap();
+        assert_eq!(time.to_string(), "09:05");
+        assert_eq!(time.to_string().parse(), Ok(time));
+        assert_eq!(TimeOfDay::new(24, 0), None);
+    }
+}
@@ -1,7 +1,17 @@
 // A terminal user interface to allow user to manage tasks more efficently
+use crate::cl::ListArg;
+use crate::config::Config;
+use crate::date::{self, DateContext};
+use crate::error::PaskError;
+use crate::filter::Filter;
+use crate::storage::{ListLock, Storage};
+use crate::lists;
+use crate::period::Period;
+use crate::tasks::Priority;
 use crate::Tasks;
 use crate::Task;
 
+use chrono::{Local, NaiveDate};
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
     execute,
@@ -11,7 +21,7 @@ use std::io;
 use tui::{
     backend::{Backend, CrosstermBackend},
     layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Span, Spans, Text},
     widgets::{Block, Borders, List, ListItem, Paragraph},
     Frame, Terminal,
@@ -23,10 +33,13 @@ enum InputMode {
     Normal,
     Insert,
     Edit,   // Edit will define 
+    Date,   // Typing the date of a list to open
+    Move,   // Typing the list to move the selected task to
+    Filter, // Typing the tags and project of the tasks to show
 }
 
 /// Holds the info for each task
-struct AppInfo {
+struct AppInfo<'a> {
     // Current task that will be added 
     task: Task, 
 
@@ -36,24 +49,169 @@ struct AppInfo {
     // Current mode 
     mode: InputMode,
 
-    // Represents the current row of the cursor
+    // Represents the current row of the cursor, among the tasks that are shown
     cursor_row: u16,
+
+    // Only tasks matching this are shown, it stays the same when other lists are opened
+    filter: Filter,
+
+    // Keys, colors and formats to use
+    config: &'a Config,
+
+    // Where lists are loaded from and saved to
+    storage: &'a dyn Storage,
+
+    // Type of list that is open and the date it was opened for
+    list: ListArg,
+    date: NaiveDate,
+    file_name: String,
+
+    // Lock on the open list, replaced when another list is opened
+    _lock: ListLock,
+
+    // Lists that were empty and still are are not saved, so browsing dates leaves no files behind
+    loaded_empty: bool,
+
+    // Text being typed in date and move mode
+    prompt: String,
+
+    // Error shown in the help bar until the next key press
+    message: Option<String>,
 }
 
-impl AppInfo {
-    pub fn new(tasks: Tasks) -> Self {
-        Self {
+impl<'a> AppInfo<'a> {
+    pub fn new(storage: &'a dyn Storage, list: ListArg, date: NaiveDate, config: &'a Config) -> Result<Self, PaskError> {
+        let file_name = lists::key(storage, &list, date, config.week_start)?;
+        let lock = storage.lock(&file_name)?;
+        let mut tasks = Tasks::get_tasks(storage, &file_name)?;
+        tasks.sort(config.sort);
+        let tasks_empty = tasks.tasks.is_empty();
+
+        Ok(Self {
             task: Task::new(),
             tasks, 
             mode: InputMode::Normal,
             cursor_row: 0,
+            filter: Filter::default(),
+            config,
+            storage,
+            list,
+            date,
+            file_name,
+            _lock: lock,
+            loaded_empty: tasks_empty,
+            prompt: String::new(),
+            message: None,
+        })
+    }
+
+    /// Saves the open list and opens the one covering `date` instead.
+    fn open(&mut self, date: NaiveDate) -> Result<(), PaskError> {
+        let file_name = lists::key(self.storage, &self.list, date, self.config.week_start)?;
+        if file_name != self.file_name {
+            self.save()?;
+            let lock = self.storage.lock(&file_name)?;
+            self.tasks = Tasks::get_tasks(self.storage, &file_name)?;
+            self.tasks.sort(self.config.sort);
+            self.loaded_empty = self.tasks.tasks.is_empty();
+            self._lock = lock;
+            self.file_name = file_name;
+            self.cursor_row = 0;
+        }
+        self.date = date;
+        Ok(())
+    }
+
+    /// Writes the open list back unless nothing was ever in it.
+    fn save(&mut self) -> Result<(), PaskError> {
+        if self.loaded_empty && self.tasks.tasks.is_empty() {
+            return Ok(());
+        }
+        self.tasks.write_tasks(self.storage, &self.file_name)
+    }
+
+    /// Gets the indexes of the tasks that are shown.
+    fn visible(&self) -> Vec<usize> {
+        (0..self.tasks.tasks.len()).filter(|&i| self.filter.matches(&self.tasks.tasks[i])).collect()
+    }
+
+    /// Gets the index of the task under the cursor, if any are shown.
+    fn selected(&self) -> Option<usize> {
+        self.visible().get(usize::from(self.cursor_row)).copied()
+    }
+
+    /// Moves the cursor to a task, if it is shown.
+    fn select(&mut self, idx: usize) {
+        if let Some(row) = self.visible().iter().position(|&x| x == idx) {
+            self.cursor_row = row as u16;
+        }
+    }
+
+    /// Keeps the cursor on a shown task after one is removed or hidden.
+    fn clamp_cursor(&mut self) {
+        let shown = self.visible().len();
+        if usize::from(self.cursor_row) >= shown {
+            self.cursor_row = shown.saturating_sub(1) as u16;
+        }
+    }
+
+    /// Moves the selected task to a list given as its name and optionally a date, such as `week`
+    /// or `day tomorrow`. Lists are for the open date when no date is given.
+    fn move_selected(&mut self, target: &str) -> Result<(), PaskError> {
+        let (list, date) = match target.trim().split_once(' ') {
+            Some((list, date)) => (list, Some(date)),
+            None => (target.trim(), None),
+        };
+        let list: ListArg = list.parse().map_err(PaskError::Validation)?;
+        let date = match date {
+            Some(text) => {
+                let context = DateContext { today: Local::now().date_naive(), week_start: self.config.week_start };
+                date::parse_date(text, context).map_err(PaskError::Validation)?
+            },
+            None => self.date,
+        };
+
+        let to = lists::key(self.storage, &list, date, self.config.week_start)?;
+        if to == self.file_name {
+            return Err(PaskError::Validation(format!("The task is already in {}", to)));
+        }
+        let idx = self.selected().ok_or_else(|| PaskError::Validation("No task is selected".to_owned()))?;
+        self.tasks.move_task_idx(idx, self.storage, &to, self.config.sort)?;
+
+        // Save straight away so the task is never left in both lists
+        self.loaded_empty = false;
+        self.save()?;
+        self.clamp_cursor();
+        Ok(())
+    }
+
+    /// Gives the selected task the next priority, keeping the cursor on it as the list is sorted
+    /// again.
+    fn cycle_priority(&mut self, idx: usize) {
+        let task = &mut self.tasks.tasks[idx];
+        task.priority = Priority::cycle(task.priority);
+        let id = task.id.clone();
+
+        self.tasks.sort(self.config.sort);
+        if let Some(idx) = self.tasks.tasks.iter().position(|x| x.id == id) {
+            self.select(idx);
+        }
+    }
+
+    /// Opens the list covering `date`, showing what went wrong if it could not be opened.
+    fn go_to(&mut self, date: NaiveDate) {
+        if let Err(e) = self.open(date) {
+            self.message = Some(e.to_string());
         }
     }
 }
 
 
-/// Will run the terminal gui on the given struct of tasks.
-pub fn run_ui(tasks: Tasks) -> Result<Tasks, io::Error>{
+/// Will run the terminal gui on the list of the given type covering `date`, saving each list it opens.
+pub fn run_ui(storage: &dyn Storage, list: ListArg, date: NaiveDate, config: &Config) -> Result<(), PaskError> {
+    // Creates a struct that represents the info the app needs 
+    let mut app_info = AppInfo::new(storage, list, date, config)?;
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
@@ -61,13 +219,10 @@ pub fn run_ui(tasks: Tasks) -> Result<Tasks, io::Error>{
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // Creates a struct that represents the info the app needs 
-    let mut app_info = AppInfo::new(tasks);
-
     // Runs the app 
-    ui_loop(&mut terminal, &mut app_info).unwrap();
+    let result = ui_loop(&mut terminal, &mut app_info);
 
-    // Close out of the app properly
+    // Close out of the app properly, even if the loop failed
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
@@ -75,76 +230,167 @@ pub fn run_ui(tasks: Tasks) -> Result<Tasks, io::Error>{
         DisableMouseCapture,
     )?;
     terminal.show_cursor()?;
+    result?;
 
-    Ok(app_info.tasks) 
+    app_info.save()
 }
 
 /// The main loop for the ui 
 fn ui_loop<T: Backend>(terminal: &mut Terminal<T>, app_info: &mut AppInfo) -> io::Result<()> {
     loop {
-        terminal.draw(|f| draw_ui(f, &app_info))?;
+        terminal.draw(|f| draw_ui(f, app_info))?;
 
         if let Event::Key(key) = event::read()? {
+            let keys = &app_info.config.keys;
+            app_info.message = None;
             match app_info.mode {
                 InputMode::Insert => match key.code {
+                    x if x == keys.back => {
+                        app_info.mode = InputMode::Normal
+                    },
+                    KeyCode::Enter => {
+                        app_info.task.take_labels();
+                        app_info.tasks.add_task(app_info.task.clone(), app_info.config.sort);
+                        app_info.task = Task::new();
+                    },
                     KeyCode::Char(c) => {
                         app_info.task.desc.push(c);
                     },
                     KeyCode::Backspace => {
                         app_info.task.desc.pop();
                     },
-                    KeyCode::Esc => {
-                        app_info.mode = InputMode::Normal
-                    }
-                    KeyCode::Enter => {
-                        app_info.tasks.add_task(app_info.task.clone());
-                        app_info.task = Task::new();
-                    }
                     _ => {},
                 },
                 InputMode::Normal => match key.code {
-                    KeyCode::Char('i') => {
+                    x if x == keys.insert => {
                         app_info.mode = InputMode::Insert;
                     },
-                    KeyCode::Char('q') => {
+                    x if x == keys.quit => {
                         return Ok(());
                     },
-                    KeyCode::Char('e') => {
+                    x if x == keys.edit => {
                         app_info.mode = InputMode::Edit;
                     },
+                    x if x == keys.previous || x == KeyCode::Left => {
+                        app_info.go_to(date::step(app_info.date, &app_info.list, -1));
+                    },
+                    x if x == keys.next || x == KeyCode::Right => {
+                        app_info.go_to(date::step(app_info.date, &app_info.list, 1));
+                    },
+                    x if x == keys.goto => {
+                        app_info.mode = InputMode::Date;
+                        app_info.prompt.clear();
+                    },
+                    x if x == keys.filter => {
+                        app_info.mode = InputMode::Filter;
+                        app_info.prompt = app_info.filter.to_string();
+                    },
 
                     _ => {},
                 },
 
-                InputMode::Edit => match key.code {
-                    KeyCode::Esc => {
+                InputMode::Edit => match (key.code, app_info.selected()) {
+                    (x, _) if x == keys.back => {
                         app_info.mode = InputMode::Normal;
                         app_info.cursor_row = 0;
                     },
                     // Will delete the tasks hovered on 
-                    KeyCode::Char('d') => {
-                        if !app_info.tasks.tasks.is_empty() {
-                            app_info.tasks.del_task_idx(app_info.cursor_row as usize);
-                            if app_info.cursor_row > 0 {
-                                app_info.cursor_row -= 1;
-                            }
+                    (x, Some(idx)) if x == keys.delete => {
+                        app_info.tasks.del_task_idx(idx);
+                        if app_info.cursor_row > 0 {
+                            app_info.cursor_row -= 1;
                         }
                     },
+                    (x, Some(idx)) if x == keys.complete => {
+                        app_info.tasks.complete_task_idx(idx);
+                    },
+                    (x, Some(idx)) if x == keys.priority => {
+                        app_info.cycle_priority(idx);
+                    },
+                    (x, Some(_)) if x == keys.move_to => {
+                        app_info.mode = InputMode::Move;
+                        app_info.prompt.clear();
+                    },
+                    (x, _) if (x == keys.up || x == KeyCode::Up) && app_info.cursor_row > 0 => {
+                        app_info.cursor_row -= 1;
+                    },
+                    (x, _) if (x == keys.down || x == KeyCode::Down)
+                        && usize::from(app_info.cursor_row + 1) < app_info.visible().len() => {
+                        app_info.cursor_row += 1;
+                    },
+                    _ => {},
+                },
+
+                InputMode::Date => match key.code {
+                    x if x == keys.back => {
+                        app_info.mode = InputMode::Normal;
+                    },
                     KeyCode::Enter => {
-                        if !app_info.tasks.tasks.is_empty() {
-                            app_info.tasks.complete_task_idx(app_info.cursor_row as usize);
+                        let context = DateContext {
+                            today: Local::now().date_naive(),
+                            week_start: app_info.config.week_start,
+                        };
+                        match date::parse_date(&app_info.prompt, context) {
+                            Ok(date) => {
+                                app_info.go_to(date);
+                                app_info.mode = InputMode::Normal;
+                            },
+                            Err(e) => app_info.message = Some(e),
                         }
                     },
-                    KeyCode::Up | KeyCode::Char('k') => {
-                        if app_info.cursor_row > 0 {
-                            app_info.cursor_row -= 1;
+                    KeyCode::Char(c) => {
+                        app_info.prompt.push(c);
+                    },
+                    KeyCode::Backspace => {
+                        app_info.prompt.pop();
+                    },
+                    _ => {},
+                },
+
+                InputMode::Filter => match key.code {
+                    x if x == keys.back => {
+                        app_info.mode = InputMode::Normal;
+                    },
+                    KeyCode::Enter => {
+                        let context = DateContext {
+                            today: Local::now().date_naive(),
+                            week_start: app_info.config.week_start,
+                        };
+                        match Filter::parse(&app_info.prompt, context) {
+                            Ok(filter) => {
+                                app_info.filter = filter;
+                                app_info.cursor_row = 0;
+                                app_info.mode = InputMode::Normal;
+                            },
+                            Err(e) => app_info.message = Some(e),
                         }
                     },
-                    KeyCode::Down | KeyCode::Char('j') => {
-                        if usize::from(app_info.cursor_row + 1) < app_info.tasks.tasks.len() {
-                            app_info.cursor_row += 1;
+                    KeyCode::Char(c) => {
+                        app_info.prompt.push(c);
+                    },
+                    KeyCode::Backspace => {
+                        app_info.prompt.pop();
+                    },
+                    _ => {},
+                },
+
+                InputMode::Move => match key.code {
+                    x if x == keys.back => {
+                        app_info.mode = InputMode::Edit;
+                    },
+                    KeyCode::Enter => {
+                        let target = app_info.prompt.clone();
+                        match app_info.move_selected(&target) {
+                            Ok(()) => app_info.mode = InputMode::Edit,
+                            Err(e) => app_info.message = Some(e.to_string()),
                         }
                     },
+                    KeyCode::Char(c) => {
+                        app_info.prompt.push(c);
+                    },
+                    KeyCode::Backspace => {
+                        app_info.prompt.pop();
+                    },
                     _ => {},
                 },
             }
@@ -169,23 +415,34 @@ fn draw_ui<T: Backend>(f: &mut Frame<T>, app_info: &AppInfo) {
         .split(f.size());
 
     // Determines the help bar based on what mode you are in
+    let keys = &app_info.config.keys;
+    let bold = |key: KeyCode| Span::styled(key_label(key), Style::default().add_modifier(Modifier::BOLD));
     let (msg, style) = match app_info.mode {
         InputMode::Normal => (
             vec![
                 Span::raw("Press "),
-                Span::styled("q", Style::default().add_modifier(Modifier::BOLD)),
+                bold(keys.quit),
                 Span::raw(" to exit, "),
-                Span::styled("i", Style::default().add_modifier(Modifier::BOLD)),
+                bold(keys.insert),
                 Span::raw(" to start inserting a task,"),
-                Span::styled("e", Style::default().add_modifier(Modifier::BOLD)),
-                Span::raw(" to start editing tasks"),
+                bold(keys.edit),
+                Span::raw(" to start editing tasks, "),
+                Span::styled(
+                    format!("{}, {}", key_label(keys.previous), key_label(keys.next)),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" to open the previous or next list, "),
+                bold(keys.goto),
+                Span::raw(" to open the list for a date, "),
+                bold(keys.filter),
+                Span::raw(" to filter the tasks"),
             ],
             Style::default().add_modifier(Modifier::RAPID_BLINK),
         ),
         InputMode::Insert => (
             vec![
                 Span::raw("Press "),
-                Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
+                bold(keys.back),
                 Span::raw(" to stop editing, and start changing tasks"),
                 Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
                 Span::raw(" to record the message"),
@@ -195,49 +452,107 @@ fn draw_ui<T: Backend>(f: &mut Frame<T>, app_info: &AppInfo) {
         InputMode::Edit => (
             vec![
                 Span::raw("Press "),
-                Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
+                bold(keys.back),
                 Span::raw(" to stop editing, and start changing tasks, "),
-                Span::styled("J, K", Style::default().add_modifier(Modifier::BOLD)),
+                Span::styled(
+                    format!("{}, {}", key_label(keys.down), key_label(keys.up)),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
                 Span::raw(" to move the cursor up and down(can also use arrow keys ), "),
-                Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
+                bold(keys.complete),
                 Span::raw(" to complete / uncomplete the task you are hovering over "),
-                Span::styled("d", Style::default().add_modifier(Modifier::BOLD)),
-                Span::raw(" to delete task"),
+                bold(keys.delete),
+                Span::raw(" to delete task, "),
+                bold(keys.priority),
+                Span::raw(" to change its priority, "),
+                bold(keys.move_to),
+                Span::raw(" to move it to another list"),
+            ],
+            Style::default(),
+        ),
+        InputMode::Move => (
+            vec![
+                Span::raw("Type a list and optionally a date such as week, goals or day tomorrow and press "),
+                Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to move the task there, "),
+                bold(keys.back),
+                Span::raw(" to cancel"),
+            ],
+            Style::default(),
+        ),
+        InputMode::Filter => (
+            vec![
+                Span::raw("Type a filter such as status:todo +work, or nothing to show every task, and press "),
+                Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to show them, "),
+                bold(keys.back),
+                Span::raw(" to cancel"),
+            ],
+            Style::default(),
+        ),
+        InputMode::Date => (
+            vec![
+                Span::raw("Type a date such as 2026-11-03, tomorrow or -1w and press "),
+                Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to open its list, "),
+                bold(keys.back),
+                Span::raw(" to cancel"),
             ],
             Style::default(),
         ),
     };
+    let (msg, style) = match &app_info.message {
+        Some(message) => (vec![Span::raw(message.clone())], Style::default().fg(app_info.config.colors.overdue)),
+        None => (msg, style),
+    };
     let mut text = Text::from(Spans::from(msg));
     text.patch_style(style);
     let help_message = Paragraph::new(text);
     f.render_widget(help_message, chunks[0]);
 
     // Create the input box 
-    let input = Paragraph::new(app_info.task.desc.as_ref())
+    let (input_text, input_title) = match app_info.mode {
+        InputMode::Date => (&app_info.prompt, "Go to date"),
+        InputMode::Move => (&app_info.prompt, "Move to list"),
+        InputMode::Filter => (&app_info.prompt, "Filter"),
+        _ => (&app_info.task.desc, "Enter Task"),
+    };
+    let input = Paragraph::new(input_text.as_ref())
         .style(match app_info.mode {
-            InputMode::Insert => Style::default().fg(Color::LightBlue),
-            _ => Style::default().fg(Color::Blue),
+            InputMode::Insert | InputMode::Date | InputMode::Move | InputMode::Filter => {
+                Style::default().fg(app_info.config.colors.input)
+            },
+            _ => Style::default().fg(app_info.config.colors.input_inactive),
         })
-        .block(Block::default().borders(Borders::ALL).title("Enter Task"));
+        .block(Block::default().borders(Borders::ALL).title(input_title));
     f.render_widget(input, chunks[1]);
 
 
     // Displays the task in a new chunk
     let mut tasks_disp: Vec<ListItem> = vec![];
-    for task in &app_info.tasks.tasks {
-        tasks_disp.push(ListItem::new(format!("{}", task)));
+    let now = Local::now().naive_local();
+    let day = Period::new(&app_info.list, app_info.date, app_info.config.week_start).day_for(now);
+    for task in app_info.visible().into_iter().map(|i| &app_info.tasks.tasks[i]) {
+        let color = app_info.config.colors.timing(task.timing(now, day));
+        tasks_disp.push(ListItem::new(task.format(&app_info.config.time_format))
+            .style(Style::default().fg(color)));
     }
+    let title = if app_info.filter.is_empty() {
+        format!("Tasks ({})", app_info.file_name)
+    } else {
+        format!("Tasks ({}, {})", app_info.file_name, app_info.filter)
+    };
     let tasks_list = List::new(tasks_disp)
-        .block(Block::default().borders(Borders::ALL).title("Tasks"));
+        .block(Block::default().borders(Borders::ALL).title(title));
     f.render_widget(tasks_list, chunks[2]);
 
     // Turn on the cursor 
     match app_info.mode {
-        InputMode::Insert => {
+        InputMode::Insert | InputMode::Date | InputMode::Move | InputMode::Filter => {
             // Make the cursor visible and ask tui-rs to put it at the specified coordinates after rendering
             f.set_cursor(
                 // Put cursor past the end of the input text
-                chunks[1].x + app_info.task.desc.width() as u16 + 1,
+                chunks[1].x + input_text.width() as u16 + 1,
                 // Move one line down, from the border to the input line
                 chunks[1].y + 1,
             )
@@ -253,3 +568,12 @@ fn draw_ui<T: Backend>(f: &mut Frame<T>, app_info: &AppInfo) {
         _ => {},
     }
 }
+
+/// Gets the name of a key as it is shown in the help bar.
+fn key_label(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => "Space".to_owned(),
+        KeyCode::Char(c) => c.to_string(),
+        _ => format!("{:?}", key),
+    }
+}